@@ -3,4 +3,6 @@ cd ./client
 npm i
 npm run build-dev
 cd ../server
+# Commit hash to report in the status endpoint.
+export BUILD_COMMIT=$(git rev-parse --short HEAD)
 cargo build
\ No newline at end of file
@@ -18,7 +18,7 @@ const deserializeShowInfo = (data: any): ShowInfo => {
 };
 
 export const getPodcast = async (podcastNum: number): Promise<ShowInfo> => {
-  return deserializeShowInfo((await axios.get(`/api/podcasts/${podcastNum}`)).data);
+  return deserializeShowInfo((await axios.get(`/api/v1/podcasts/${podcastNum}`)).data);
 };
 
 interface SearchResult {
@@ -57,7 +57,7 @@ async (data: {
     queryParams[maxLengthSecondsFieldName] = data.maxLengthSeconds;
   }
 
-  const res = await axios.get(generateUrlWithQueryParams('/api/search/podcasts', queryParams)) as any;
+  const res = await axios.get(generateUrlWithQueryParams('/api/v1/search/podcasts', queryParams)) as any;
   return {...res.data, hits: res.data.hits.map(deserializeShowInfo)};
 };
 
@@ -81,7 +81,7 @@ export const getPodcastRssUrl = (data: {
     queryParams[maxLengthSecondsFieldName] = data.maxLengthSeconds;
   }
 
-  return encodeURI(generateUrlWithQueryParams('https://fdr-finder.tommyvolk.com/api/search/podcasts/rss', queryParams));
+  return encodeURI(generateUrlWithQueryParams('https://fdr-finder.tommyvolk.com/api/v1/search/podcasts/rss', queryParams));
 }
 
 export const getFilteredTagsWithCounts =
@@ -117,7 +117,7 @@ async (data: {
     queryParams[filterFieldName] = data.filter;
   }
 
-  return (await axios.get(generateUrlWithQueryParams('/api/filteredTagsWithCounts', queryParams))).data as any;
+  return (await axios.get(generateUrlWithQueryParams('/api/v1/search/tags', queryParams))).data as any;
 }
 
 export const generateUrlWithQueryParams =
@@ -2,6 +2,6 @@ export const queryFieldName = 'query';
 export const limitFieldName = 'limit';
 export const offsetFieldName = 'offset';
 export const tagsFieldName = 'tags';
-export const minLengthSecondsFieldName = 'min_length_seconds';
-export const maxLengthSecondsFieldName = 'max_length_seconds';
+export const minLengthSecondsFieldName = 'minLengthSeconds';
+export const maxLengthSecondsFieldName = 'maxLengthSeconds';
 export const filterFieldName = 'filter';
\ No newline at end of file
@@ -1,4 +1,6 @@
 set -e # If any stage fails, don't continue.
 sh build.sh
 cd ./server
+# Must match the value set by the build script, otherwise Cargo rebuilds the server without it.
+export BUILD_COMMIT=$(git rev-parse --short HEAD)
 cargo run
\ No newline at end of file
@@ -0,0 +1,423 @@
+//! Aggregate statistics over sets of podcasts, used to power charts without
+//! clients having to download the whole catalogue.
+
+use crate::podcast::{Podcast, PodcastTag};
+use chrono::{Datelike, TimeZone, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use utoipa::ToSchema;
+
+/// Length of time that podcasts are grouped into.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Period {
+    Year,
+    Month,
+}
+
+impl std::str::FromStr for Period {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "year" => Ok(Self::Year),
+            "month" => Ok(Self::Month),
+            _ => Err(()),
+        }
+    }
+}
+
+// Year and month (1-12) of a period. The month is always 0 for yearly periods.
+type PeriodKey = (i32, u32);
+
+impl Period {
+    fn get_key(&self, podcast: &Podcast) -> Option<PeriodKey> {
+        let create_time = Utc.timestamp_opt(podcast.get_create_time(), 0).single()?;
+        match self {
+            Self::Year => Some((create_time.year(), 0)),
+            Self::Month => Some((create_time.year(), create_time.month())),
+        }
+    }
+
+    fn get_next_key(&self, (year, month): PeriodKey) -> PeriodKey {
+        match self {
+            Self::Year => (year + 1, 0),
+            Self::Month if month == 12 => (year + 1, 1),
+            Self::Month => (year, month + 1),
+        }
+    }
+
+    /// Every period from the first to the last key, inclusive.
+    fn get_key_range(&self, first_key: PeriodKey, last_key: PeriodKey) -> Vec<PeriodKey> {
+        let mut keys = vec![first_key];
+        while keys[keys.len() - 1] < last_key {
+            keys.push(self.get_next_key(keys[keys.len() - 1]));
+        }
+        keys
+    }
+
+    fn format_key(&self, (year, month): PeriodKey) -> String {
+        match self {
+            Self::Year => format!("{}", year),
+            Self::Month => format!("{}-{:02}", year, month),
+        }
+    }
+
+    /// Groups podcasts by period, including empty periods between the first
+    /// and last podcast. Podcasts with an invalid publish time are left out.
+    fn group_podcasts<'a>(&self, podcasts: &[&'a Podcast]) -> Vec<(String, Vec<&'a Podcast>)> {
+        let mut podcasts_by_key: BTreeMap<PeriodKey, Vec<&Podcast>> = BTreeMap::new();
+        for podcast in podcasts {
+            if let Some(key) = self.get_key(podcast) {
+                podcasts_by_key.entry(key).or_default().push(podcast);
+            }
+        }
+
+        let (first_key, last_key) = match (
+            podcasts_by_key.keys().next(),
+            podcasts_by_key.keys().next_back(),
+        ) {
+            (Some(first_key), Some(last_key)) => (*first_key, *last_key),
+            _ => return Vec::new(),
+        };
+
+        self.get_key_range(first_key, last_key)
+            .into_iter()
+            .map(|key| {
+                (
+                    self.format_key(key),
+                    podcasts_by_key.remove(&key).unwrap_or_default(),
+                )
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PeriodStats {
+    /// Formatted as `YYYY` for yearly periods and `YYYY-MM` for monthly periods.
+    period: String,
+    episode_count: usize,
+    total_length_seconds: u64,
+}
+
+/// Number of podcasts and their total length in each period.
+pub fn get_timeline(podcasts: &[&Podcast], period: Period) -> Vec<PeriodStats> {
+    period
+        .group_podcasts(podcasts)
+        .into_iter()
+        .map(|(period, podcasts)| PeriodStats {
+            period,
+            episode_count: podcasts.len(),
+            total_length_seconds: podcasts
+                .iter()
+                .map(|podcast| podcast.get_length_in_seconds().max(0) as u64)
+                .sum(),
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagTrend {
+    #[schema(value_type = String)]
+    tag: PodcastTag,
+    total_count: usize,
+    /// Number of podcasts with the tag in each period, in the same order as `periods`.
+    counts: Vec<usize>,
+}
+
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagTrends {
+    periods: Vec<String>,
+    tags: Vec<TagTrend>,
+}
+
+/// Usage over time of the `top` most used tags.
+pub fn get_tag_trends(podcasts: &[&Podcast], period: Period, top: usize) -> TagTrends {
+    let grouped_podcasts = period.group_podcasts(podcasts);
+
+    let mut counts_by_tag: HashMap<&PodcastTag, Vec<usize>> = HashMap::new();
+    for (i, (_, podcasts)) in grouped_podcasts.iter().enumerate() {
+        for podcast in podcasts {
+            for tag in podcast.get_tags() {
+                counts_by_tag
+                    .entry(tag)
+                    .or_insert_with(|| vec![0; grouped_podcasts.len()])[i] += 1;
+            }
+        }
+    }
+
+    let mut tags: Vec<TagTrend> = counts_by_tag
+        .into_iter()
+        .map(|(tag, counts)| TagTrend {
+            tag: tag.clone(),
+            total_count: counts.iter().sum(),
+            counts,
+        })
+        .collect();
+    tags.sort_by(|tag_one, tag_two| {
+        tag_two
+            .total_count
+            .cmp(&tag_one.total_count)
+            .then_with(|| tag_one.tag.get_key().cmp(tag_two.tag.get_key()))
+    });
+    tags.truncate(top);
+
+    TagTrends {
+        periods: grouped_podcasts
+            .into_iter()
+            .map(|(period, _)| period)
+            .collect(),
+        tags,
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LengthBucket {
+    /// Inclusive lower bound of the bucket.
+    min_length_seconds: u64,
+    /// Exclusive upper bound of the bucket.
+    max_length_seconds: u64,
+    episode_count: usize,
+}
+
+/// Number of podcasts in each `bucket_seconds` wide range of lengths, from
+/// zero up to the longest podcast.
+pub fn get_length_histogram(podcasts: &[&Podcast], bucket_seconds: u64) -> Vec<LengthBucket> {
+    let mut counts: Vec<usize> = Vec::new();
+    for podcast in podcasts {
+        let bucket_index =
+            (podcast.get_length_in_seconds().max(0) as u64 / bucket_seconds) as usize;
+        if bucket_index >= counts.len() {
+            counts.resize(bucket_index + 1, 0);
+        }
+        counts[bucket_index] += 1;
+    }
+
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, episode_count)| LengthBucket {
+            min_length_seconds: i as u64 * bucket_seconds,
+            max_length_seconds: (i as u64 + 1) * bucket_seconds,
+            episode_count,
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagNode {
+    #[schema(value_type = String)]
+    tag: PodcastTag,
+    /// Number of podcasts with the tag.
+    count: usize,
+}
+
+/// Undirected edge between two tags. `source` always sorts before `target`.
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagEdge {
+    #[schema(value_type = String)]
+    source: PodcastTag,
+    #[schema(value_type = String)]
+    target: PodcastTag,
+    /// Number of podcasts with both tags.
+    weight: usize,
+}
+
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagGraph {
+    nodes: Vec<TagNode>,
+    edges: Vec<TagEdge>,
+}
+
+/// Graph of which tags appear on the same podcasts. Only the `max_nodes` most
+/// used tags that appear at least `min_count` times are included, along with
+/// the edges between them that have a weight of at least `min_weight`.
+pub fn get_tag_graph(
+    podcasts: &[&Podcast],
+    min_count: usize,
+    min_weight: usize,
+    max_nodes: usize,
+) -> TagGraph {
+    let mut counts_by_tag: HashMap<&PodcastTag, usize> = HashMap::new();
+    for podcast in podcasts {
+        for tag in podcast.get_tags() {
+            *counts_by_tag.entry(tag).or_insert(0) += 1;
+        }
+    }
+
+    let mut nodes: Vec<TagNode> = counts_by_tag
+        .into_iter()
+        .filter(|(_, count)| *count >= min_count)
+        .map(|(tag, count)| TagNode {
+            tag: tag.clone(),
+            count,
+        })
+        .collect();
+    nodes.sort_by(|node_one, node_two| {
+        node_two
+            .count
+            .cmp(&node_one.count)
+            .then_with(|| node_one.tag.get_key().cmp(node_two.tag.get_key()))
+    });
+    nodes.truncate(max_nodes);
+
+    let included_tags: HashSet<&PodcastTag> = nodes.iter().map(|node| &node.tag).collect();
+    let mut weights_by_pair: HashMap<(&PodcastTag, &PodcastTag), usize> = HashMap::new();
+    for podcast in podcasts {
+        let mut tags: Vec<&PodcastTag> = podcast
+            .get_tags()
+            .iter()
+            .filter(|tag| included_tags.contains(tag))
+            .collect();
+        tags.sort_by(|tag_one, tag_two| tag_one.to_string().cmp(tag_two.to_string()));
+        for (i, source) in tags.iter().enumerate() {
+            for target in &tags[i + 1..] {
+                *weights_by_pair.entry((source, target)).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut edges: Vec<TagEdge> = weights_by_pair
+        .into_iter()
+        .filter(|(_, weight)| *weight >= min_weight)
+        .map(|((source, target), weight)| TagEdge {
+            source: source.clone(),
+            target: target.clone(),
+            weight,
+        })
+        .collect();
+    edges.sort_by(|edge_one, edge_two| {
+        edge_two
+            .weight
+            .cmp(&edge_one.weight)
+            .then_with(|| edge_one.source.to_string().cmp(edge_two.source.to_string()))
+            .then_with(|| edge_one.target.to_string().cmp(edge_two.target.to_string()))
+    });
+
+    TagGraph { nodes, edges }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::create_test_podcast;
+
+    fn timestamp(time: &str) -> i64 {
+        chrono::DateTime::parse_from_rfc3339(time)
+            .unwrap()
+            .timestamp()
+    }
+
+    #[test]
+    fn test_analytics() {
+        let podcasts = [
+            create_test_podcast("1", "", 100, timestamp("2010-11-30T12:00:00Z"), &["foo"]),
+            create_test_podcast(
+                "2",
+                "",
+                700,
+                timestamp("2011-01-15T12:00:00Z"),
+                &["foo", "bar"],
+            ),
+            create_test_podcast("3", "", 50, timestamp("2011-01-20T12:00:00Z"), &["bar"]),
+        ];
+        let podcasts: Vec<&Podcast> = podcasts.iter().collect();
+
+        assert_eq!(
+            get_timeline(&podcasts, Period::Year),
+            vec![
+                PeriodStats {
+                    period: "2010".to_string(),
+                    episode_count: 1,
+                    total_length_seconds: 100
+                },
+                PeriodStats {
+                    period: "2011".to_string(),
+                    episode_count: 2,
+                    total_length_seconds: 750
+                }
+            ]
+        );
+        // Empty months are filled in.
+        let monthly_periods: Vec<String> = get_timeline(&podcasts, Period::Month)
+            .into_iter()
+            .map(|stats| stats.period)
+            .collect();
+        assert_eq!(monthly_periods, vec!["2010-11", "2010-12", "2011-01"]);
+
+        assert_eq!(
+            get_tag_trends(&podcasts, Period::Year, 1),
+            TagTrends {
+                periods: vec!["2010".to_string(), "2011".to_string()],
+                tags: vec![TagTrend {
+                    tag: PodcastTag::new("bar".to_string()),
+                    total_count: 2,
+                    counts: vec![0, 2]
+                }]
+            }
+        );
+
+        let histogram = get_length_histogram(&podcasts, 600);
+        assert_eq!(
+            histogram
+                .iter()
+                .map(|bucket| bucket.episode_count)
+                .collect::<Vec<usize>>(),
+            vec![2, 1]
+        );
+        assert_eq!(histogram[1].min_length_seconds, 600);
+        assert_eq!(histogram[1].max_length_seconds, 1200);
+
+        assert!(get_timeline(&[], Period::Year).is_empty());
+    }
+
+    #[test]
+    fn test_get_tag_graph() {
+        let podcasts = [
+            create_test_podcast("1", "", 0, 0, &["a", "b", "c"]),
+            create_test_podcast("2", "", 0, 0, &["a", "b"]),
+            create_test_podcast("3", "", 0, 0, &["a", "d"]),
+        ];
+        let podcasts: Vec<&Podcast> = podcasts.iter().collect();
+        let tag = |tag: &str| PodcastTag::new(tag.to_string());
+
+        let graph = get_tag_graph(&podcasts, 2, 2, 10);
+        assert_eq!(
+            graph,
+            TagGraph {
+                nodes: vec![
+                    TagNode {
+                        tag: tag("a"),
+                        count: 3
+                    },
+                    TagNode {
+                        tag: tag("b"),
+                        count: 2
+                    }
+                ],
+                edges: vec![TagEdge {
+                    source: tag("a"),
+                    target: tag("b"),
+                    weight: 2
+                }]
+            }
+        );
+
+        // Edges are only included between nodes that made the cut.
+        let graph = get_tag_graph(&podcasts, 1, 1, 1);
+        assert_eq!(graph.nodes.len(), 1);
+        assert!(graph.edges.is_empty());
+
+        let graph = get_tag_graph(&podcasts, 1, 1, 10);
+        assert_eq!(graph.nodes.len(), 4);
+        assert_eq!(graph.edges.len(), 4);
+    }
+}
@@ -0,0 +1,166 @@
+//! Error responses for the API, serialized as RFC 7807 `application/problem+json` documents.
+
+use rocket::http::{ContentType, Status};
+use rocket::Request;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Machine-readable reason for an API error, so that clients can tell
+/// error cases apart without parsing the human-readable `detail` message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiErrorCode {
+    /// The request is malformed.
+    BadRequest,
+    /// A parameter has a value that's invalid or out of range.
+    InvalidParameter,
+    /// No API route exists at the requested path.
+    RouteNotFound,
+    PodcastNotFound,
+    FeedNotFound,
+    TagNotFound,
+    SeriesNotFound,
+    TranscriptNotFound,
+    /// The server isn't configured to proxy audio.
+    AudioProxyDisabled,
+    /// The host of a podcast's audio failed to serve it.
+    AudioUnavailable,
+    /// The search backend failed to handle the request.
+    SearchBackendError,
+    InternalError,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiError {
+    /// Always `about:blank`, since `code` already identifies the type of error.
+    #[serde(rename = "type")]
+    problem_type: &'static str,
+    /// Reason phrase of the HTTP status code.
+    title: &'static str,
+    status: u16,
+    detail: String,
+    code: ApiErrorCode,
+    /// Path of the request that caused the error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+}
+
+impl ApiError {
+    pub fn new(status: Status, code: ApiErrorCode, detail: impl Into<String>) -> Self {
+        Self {
+            problem_type: "about:blank",
+            title: status.reason_lossy(),
+            status: status.code,
+            detail: detail.into(),
+            code,
+            instance: None,
+        }
+    }
+
+    pub fn bad_request(code: ApiErrorCode, detail: impl Into<String>) -> Self {
+        Self::new(Status::BadRequest, code, detail)
+    }
+
+    pub fn not_found(code: ApiErrorCode, detail: impl Into<String>) -> Self {
+        Self::new(Status::NotFound, code, detail)
+    }
+
+    pub fn internal(detail: impl Into<String>) -> Self {
+        Self::new(
+            Status::InternalServerError,
+            ApiErrorCode::InternalError,
+            detail,
+        )
+    }
+
+    pub fn audio_proxy_disabled() -> Self {
+        Self::not_found(
+            ApiErrorCode::AudioProxyDisabled,
+            "The audio proxy is disabled on this server.",
+        )
+    }
+
+    pub fn search_backend(error: impl std::fmt::Display) -> Self {
+        Self::new(
+            Status::ServiceUnavailable,
+            ApiErrorCode::SearchBackendError,
+            format!("Search is currently unavailable: {}", error),
+        )
+    }
+}
+
+impl<'r> rocket::response::Responder<'r, 'static> for ApiError {
+    fn respond_to(
+        mut self,
+        request: &'r Request,
+    ) -> Result<rocket::response::Response<'static>, Status> {
+        if self.instance.is_none() {
+            self.instance = Some(request.uri().path().to_string());
+        }
+        let json_string = serde_json::json!(self).to_string();
+        rocket::Response::build()
+            .status(Status::from_code(self.status).unwrap_or(Status::InternalServerError))
+            .header(ContentType::new("application", "problem+json"))
+            .sized_body(json_string.len(), std::io::Cursor::new(json_string))
+            .ok()
+    }
+}
+
+#[catch(400)]
+fn bad_request_catcher() -> ApiError {
+    ApiError::bad_request(ApiErrorCode::BadRequest, "The request is malformed.")
+}
+
+#[catch(404)]
+fn not_found_catcher(request: &Request) -> ApiError {
+    ApiError::not_found(
+        ApiErrorCode::RouteNotFound,
+        format!("API path '{}' does not exist.", request.uri().path()),
+    )
+}
+
+#[catch(422)]
+fn unprocessable_entity_catcher() -> ApiError {
+    ApiError::new(
+        Status::UnprocessableEntity,
+        ApiErrorCode::InvalidParameter,
+        "One or more parameters have an invalid value.",
+    )
+}
+
+#[catch(500)]
+fn internal_error_catcher() -> ApiError {
+    ApiError::internal("An unexpected error occurred.")
+}
+
+/// Catchers that return problem documents for errors that no handler dealt
+/// with. These should be registered under `/api`.
+pub fn catchers() -> Vec<rocket::Catcher> {
+    catchers![
+        bad_request_catcher,
+        not_found_catcher,
+        unprocessable_entity_catcher,
+        internal_error_catcher
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_error_serialization() {
+        let error = ApiError::not_found(ApiErrorCode::PodcastNotFound, "Podcast does not exist.");
+        assert_eq!(
+            serde_json::json!(error),
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "Podcast does not exist.",
+                "code": "podcast_not_found"
+            })
+        );
+    }
+}
@@ -0,0 +1,163 @@
+//! The original unversioned API routes. These are kept so that existing
+//! clients and feed URLs keep working, but every response is marked as
+//! deprecated. New code should use the `v1` routes instead.
+
+use super::error::ApiError;
+use super::v1::{
+    self, FilteredRequest, PodcastFilterRequest, SearchPodcastsRequest, SearchTagsRequest,
+};
+use super::Deprecated;
+use crate::environment::EnvironmentVariables;
+use crate::fdr_cache::FdrCache;
+use crate::logging::RequestId;
+use crate::podcast::{Podcast, RssFeed};
+use crate::search::{SearchBackend, SearchResult, MAX_SEARCH_LIMIT};
+use crate::tag_normalizer::TagNormalizer;
+use crate::taxonomy::Taxonomy;
+use rocket::response::content;
+use rocket::{Route, State};
+
+#[get("/reset")]
+pub async fn reset_handler(
+    request_id: RequestId,
+    search_backend: &State<SearchBackend>,
+) -> Deprecated<Result<(), ApiError>> {
+    Deprecated::new(
+        v1::reset_handler(request_id, search_backend).await,
+        "/api/v1/reset",
+    )
+}
+
+#[get("/podcasts/<podcast_num>")]
+pub fn get_podcast_handler(
+    podcast_num: String,
+    fdr_cache: &State<FdrCache>,
+) -> Deprecated<Result<Podcast, ApiError>> {
+    Deprecated::new(
+        v1::get_podcast_handler(podcast_num, fdr_cache),
+        "/api/v1/podcasts",
+    )
+}
+
+// TODO - Find a way to reduce the number of arguments so we can remove this.
+#[allow(clippy::too_many_arguments)]
+#[get("/search/podcasts?<query>&<limit>&<offset>&<tags>&<min_length_seconds>&<max_length_seconds>")]
+#[tracing::instrument(skip_all, fields(request_id = %request_id))]
+pub async fn search_podcasts_handler(
+    query: Option<String>,
+    limit: Option<String>,
+    offset: Option<String>,
+    tags: Option<String>,
+    min_length_seconds: Option<String>,
+    max_length_seconds: Option<String>,
+    request_id: RequestId,
+    search_backend: &State<SearchBackend>,
+    taxonomy: &State<Taxonomy>,
+) -> Deprecated<Result<SearchResult, ApiError>> {
+    let request = FilteredRequest {
+        filter: PodcastFilterRequest {
+            query,
+            tags,
+            category: None,
+            min_length_seconds,
+            max_length_seconds,
+        },
+        params: SearchPodcastsRequest { limit, offset },
+    };
+    // Unlike the v1 route, this has always returned as many hits as it can if no limit is set.
+    Deprecated::new(
+        v1::search_podcasts(request, MAX_SEARCH_LIMIT, search_backend, taxonomy).await,
+        "/api/v1/search/podcasts",
+    )
+}
+
+// TODO - Find a way to reduce the number of arguments so we can remove this.
+#[allow(clippy::too_many_arguments)]
+#[get("/search/podcasts/rss?<query>&<tags>&<min_length_seconds>&<max_length_seconds>")]
+pub async fn search_podcasts_as_rss_feed_handler(
+    query: Option<String>,
+    tags: Option<String>,
+    min_length_seconds: Option<String>,
+    max_length_seconds: Option<String>,
+    request_id: RequestId,
+    fdr_cache: &State<FdrCache>,
+    search_backend: &State<SearchBackend>,
+    taxonomy: &State<Taxonomy>,
+    env_vars: &State<EnvironmentVariables>,
+) -> Deprecated<Result<RssFeed, ApiError>> {
+    let request = PodcastFilterRequest {
+        query,
+        tags,
+        category: None,
+        min_length_seconds,
+        max_length_seconds,
+    };
+    Deprecated::new(
+        v1::search_podcasts_as_rss_feed_handler(
+            None,
+            request,
+            request_id,
+            fdr_cache,
+            search_backend,
+            taxonomy,
+            env_vars,
+        )
+        .await,
+        "/api/v1/search/podcasts/rss",
+    )
+}
+
+// TODO - Find a way to reduce the number of arguments so we can remove this.
+#[allow(clippy::too_many_arguments)]
+#[get("/filteredTagsWithCounts?<query>&<limit>&<offset>&<tags>&<filter>&<min_length_seconds>&<max_length_seconds>")]
+pub async fn get_filtered_tags_with_counts_handler(
+    query: Option<String>,
+    limit: Option<String>,
+    offset: Option<String>,
+    tags: Option<String>,
+    filter: Option<String>,
+    min_length_seconds: Option<String>,
+    max_length_seconds: Option<String>,
+    request_id: RequestId,
+    fdr_cache: &State<FdrCache>,
+    search_backend: &State<SearchBackend>,
+    tag_normalizer: &State<TagNormalizer>,
+    taxonomy: &State<Taxonomy>,
+) -> Deprecated<Result<content::Json<String>, ApiError>> {
+    let request = FilteredRequest {
+        filter: PodcastFilterRequest {
+            query,
+            tags,
+            category: None,
+            min_length_seconds,
+            max_length_seconds,
+        },
+        params: SearchTagsRequest {
+            limit,
+            offset,
+            filter,
+        },
+    };
+    Deprecated::new(
+        v1::search_tags_handler(
+            request,
+            request_id,
+            fdr_cache,
+            search_backend,
+            tag_normalizer,
+            taxonomy,
+        )
+        .await,
+        "/api/v1/search/tags",
+    )
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        reset_handler,
+        get_podcast_handler,
+        search_podcasts_handler,
+        search_podcasts_as_rss_feed_handler,
+        get_filtered_tags_with_counts_handler
+    ]
+}
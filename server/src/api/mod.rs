@@ -0,0 +1,105 @@
+//! HTTP API handlers.
+//!
+//! All handlers live in the versioned `v1` module, which is described by the
+//! spec in the `openapi` module. The `legacy` module keeps the original
+//! unversioned routes working as deprecated aliases that forward to their `v1`
+//! equivalents.
+
+pub mod error;
+pub mod legacy;
+pub mod openapi;
+pub mod v1;
+mod validation;
+
+use crate::fdr_cache::FdrCache;
+use crate::podcast::{generate_rss_feed, MediaType, Podcast, PodcastTag, RssFeed};
+use crate::search::{SearchBackend, SearchQuery};
+use error::ApiError;
+
+fn parse_tag_query_string(tags: Option<String>) -> Vec<PodcastTag> {
+    match tags {
+        Some(tags) => tags
+            .split(',')
+            .map(|tag| PodcastTag::new(tag.trim().to_string()))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Absolute URL of the RSS feed containing all podcasts with the given tag.
+fn get_tag_rss_feed_url(public_base_url: &str, tag: &PodcastTag) -> String {
+    // Form encoding turns spaces into `+`, which only means a space in query strings.
+    let encoded_tag = url::form_urlencoded::byte_serialize(tag.to_string().as_bytes())
+        .collect::<String>()
+        .replace('+', "%20");
+    format!("{}/api/v1/tags/{}/rss", public_base_url, encoded_tag)
+}
+
+/// Every podcast matching the search, newest first.
+async fn get_matching_podcasts(
+    search_query: &SearchQuery,
+    fdr_cache: &FdrCache,
+    search_backend: &SearchBackend,
+) -> Result<Vec<Podcast>, ApiError> {
+    search_backend
+        .search_all(search_query, fdr_cache)
+        .await
+        .map_err(ApiError::search_backend)
+}
+
+async fn generate_custom_rss_feed(
+    search_query: &SearchQuery,
+    fdr_cache: &FdrCache,
+    search_backend: &SearchBackend,
+    media_type: MediaType,
+    public_base_url: &str,
+) -> Result<RssFeed, ApiError> {
+    let podcasts = get_matching_podcasts(search_query, fdr_cache, search_backend).await?;
+
+    let query = search_query.get_query().clone().unwrap_or_default();
+
+    // TODO - Fix RSS feed naming now that we support tag filtering.
+    Ok(generate_rss_feed(
+        &podcasts,
+        &format!("Freedomain Custom Feed: {}", query),
+        &format!(
+            "A generated feed containing all Freedomain podcasts about: {}",
+            query
+        ),
+        media_type,
+        public_base_url,
+    ))
+}
+
+/// Wraps a response from a deprecated route, marking it as deprecated and
+/// pointing clients to the route that replaces it.
+pub struct Deprecated<R> {
+    inner: R,
+    successor_path: &'static str,
+}
+
+impl<R> Deprecated<R> {
+    pub fn new(inner: R, successor_path: &'static str) -> Self {
+        Self {
+            inner,
+            successor_path,
+        }
+    }
+}
+
+impl<'r, R: rocket::response::Responder<'r, 'static>> rocket::response::Responder<'r, 'static>
+    for Deprecated<R>
+{
+    fn respond_to(
+        self,
+        request: &'r rocket::request::Request,
+    ) -> Result<rocket::response::Response<'static>, rocket::http::Status> {
+        let mut response = self.inner.respond_to(request)?;
+        response.set_raw_header("Deprecation", "true");
+        response.set_raw_header(
+            "Link",
+            format!("<{}>; rel=\"successor-version\"", self.successor_path),
+        );
+        Ok(response)
+    }
+}
@@ -0,0 +1,155 @@
+//! OpenAPI 3 specification for the `v1` API, generated from the route
+//! definitions and request/response types.
+
+use super::error::{ApiError, ApiErrorCode};
+use super::v1;
+use crate::analytics::{
+    LengthBucket, Period, PeriodStats, TagEdge, TagGraph, TagNode, TagTrend, TagTrends,
+};
+use crate::audio_cache::AudioCacheStats;
+use crate::chapters::{Chapter, ChaptersDocument};
+use crate::description::DescriptionTimestamp;
+use crate::podcast::{MediaLink, MediaType, Podcast};
+use crate::saved_feeds::{FeedDefinition, SavedFeed, SavedFeedInfo, SavedFeedList};
+use crate::search::SearchResult;
+use crate::series::{Series, SeriesMembership, SeriesPart};
+use crate::tag_index::TagSummary;
+use crate::taxonomy::{TaxonomyHierarchy, TaxonomyNode, TaxonomyNodeKind};
+use crate::transcripts::{TranscriptFormat, TranscriptMatch};
+use rocket::response::content;
+use rocket::Route;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "FDR Finder API",
+        description = "Search engine for Freedomain Radio podcasts."
+    ),
+    servers((url = "/api/v1")),
+    paths(
+        v1::reset_handler,
+        v1::get_podcast_handler,
+        v1::get_podcast_chapters_handler,
+        v1::get_podcast_transcript_handler,
+        v1::get_podcast_audio_handler,
+        v1::search_podcasts_handler,
+        v1::search_podcasts_as_rss_feed_handler,
+        v1::search_tags_handler,
+        v1::get_tag_directory_handler,
+        v1::get_tag_handler,
+        v1::get_tag_rss_feed_handler,
+        v1::get_series_handler,
+        v1::create_saved_feed_handler,
+        v1::list_saved_feeds_handler,
+        v1::get_saved_feed_handler,
+        v1::get_saved_feed_as_rss_feed_handler,
+        v1::get_tag_feeds_as_opml_handler,
+        v1::get_status_handler,
+        v1::get_audio_cache_stats_handler,
+        v1::get_timeline_handler,
+        v1::get_tag_trends_handler,
+        v1::get_length_histogram_handler,
+        v1::get_tag_graph_handler,
+        v1::get_taxonomy_handler
+    ),
+    components(schemas(
+        ApiError,
+        ApiErrorCode,
+        Podcast,
+        MediaLink,
+        MediaType,
+        DescriptionTimestamp,
+        Chapter,
+        ChaptersDocument,
+        SeriesMembership,
+        Series,
+        SeriesPart,
+        SearchResult,
+        AudioCacheStats,
+        TranscriptFormat,
+        TranscriptMatch,
+        v1::TagCount,
+        v1::TagCountsResponse,
+        v1::TagDirectoryResponse,
+        v1::TagDetailsResponse,
+        TagSummary,
+        FeedDefinition,
+        SavedFeed,
+        SavedFeedInfo,
+        SavedFeedList,
+        v1::EpisodeSummary,
+        v1::SyncResult,
+        v1::StatusResponse,
+        v1::TimelineResponse,
+        v1::TagTrendsResponse,
+        v1::LengthHistogramResponse,
+        Period,
+        PeriodStats,
+        TagTrend,
+        TagTrends,
+        LengthBucket,
+        TagGraph,
+        TagNode,
+        TagEdge,
+        TaxonomyHierarchy,
+        TaxonomyNode,
+        TaxonomyNodeKind
+    ))
+)]
+pub struct ApiDoc;
+
+// Renders the spec with Redoc, which is loaded from a CDN so that we don't have to bundle it. The version is
+// pinned so that a new Redoc release can't change what this page runs without us noticing.
+// TODO - Add an `integrity` attribute with the bundle's SRI hash, or vendor the bundle into `client/out`.
+const DOCS_HTML: &str = r#"<!DOCTYPE html>
+<html>
+  <head>
+    <title>FDR Finder API</title>
+    <meta charset="utf-8"/>
+    <meta name="viewport" content="width=device-width, initial-scale=1">
+  </head>
+  <body>
+    <redoc spec-url="/api/v1/openapi.json"></redoc>
+    <script src="https://cdn.redoc.ly/redoc/v2.1.3/bundles/redoc.standalone.js" crossorigin="anonymous"></script>
+  </body>
+</html>"#;
+
+#[get("/openapi.json")]
+pub fn get_openapi_spec_handler() -> content::Json<String> {
+    // Serializing the spec can only fail if it contains non-string map keys, which it never does.
+    content::Json(ApiDoc::openapi().to_pretty_json().unwrap())
+}
+
+#[get("/docs")]
+pub fn get_docs_handler() -> content::Html<&'static str> {
+    content::Html(DOCS_HTML)
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![get_openapi_spec_handler, get_docs_handler]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_spec_references_only_known_schemas() {
+        let spec = ApiDoc::openapi();
+        let spec_json = spec.to_json().unwrap();
+        let schemas = &spec.components.unwrap().schemas;
+
+        for (index, _) in spec_json.match_indices("#/components/schemas/") {
+            let schema_name: String = spec_json[index + "#/components/schemas/".len()..]
+                .chars()
+                .take_while(|c| *c != '"')
+                .collect();
+            assert!(
+                schemas.contains_key(&schema_name),
+                "Schema '{}' is referenced but not registered",
+                schema_name
+            );
+        }
+    }
+}
@@ -0,0 +1,1199 @@
+use super::error::{ApiError, ApiErrorCode};
+use super::validation::{
+    parse_param, validate_category, validate_pagination, validate_range, validate_search_query,
+    validate_text, DEFAULT_HISTOGRAM_BUCKET_SECONDS, DEFAULT_SEARCH_LIMIT,
+    DEFAULT_TAG_DIRECTORY_LIMIT, DEFAULT_TAG_GRAPH_NODE_COUNT, DEFAULT_TAG_TREND_COUNT,
+    MAX_TAG_GRAPH_NODE_COUNT, MAX_TAG_TREND_COUNT, MIN_HISTOGRAM_BUCKET_SECONDS,
+};
+use super::{generate_custom_rss_feed, get_matching_podcasts, get_tag_rss_feed_url};
+use crate::analytics::{self, LengthBucket, Period, PeriodStats, TagTrends};
+use crate::audio_cache::{AudioCache, AudioResponse, RangeHeader};
+use crate::chapters::ChaptersDocument;
+use crate::environment::EnvironmentVariables;
+use crate::fdr_cache::FdrCache;
+use crate::logging::RequestId;
+use crate::opml::{Opml, OpmlOutline};
+use crate::podcast::{
+    generate_rss_feed, podcast_number_schema, MediaType, Podcast, PodcastNumber, PodcastTag,
+    RssFeed,
+};
+use crate::saved_feeds::{FeedDefinition, SavedFeedInfo, SavedFeedList, SavedFeedStore};
+use crate::search::{SearchBackend, SearchQuery, SearchResult};
+use crate::sync::SyncStatus;
+use crate::tag_index::{TagOrder, TagSummary};
+use crate::tag_normalizer::{normalize_text, TagNormalizer};
+use crate::taxonomy::Taxonomy;
+use crate::transcripts::{TranscriptDocument, TranscriptFormat, TranscriptStore};
+use rocket::form::{self, FromForm};
+use rocket::response::{content, status};
+use rocket::{Route, State};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::{IntoParams, ToSchema};
+
+// Number of related tags and recent episodes included in a tag's details.
+const TAG_DETAILS_RELATED_TAG_COUNT: usize = 10;
+const TAG_DETAILS_RECENT_EPISODE_COUNT: usize = 10;
+
+/// Query params for narrowing down the set of podcasts to search over.
+#[derive(FromForm, IntoParams)]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct PodcastFilterRequest {
+    pub query: Option<String>,
+    /// Comma-separated list of tags, all of which a podcast must have.
+    pub tags: Option<String>,
+    /// Name of a taxonomy category. Podcasts must have at least one tag under the category or its subcategories.
+    pub category: Option<String>,
+    #[field(name = "minLengthSeconds")]
+    #[param(value_type = Option<usize>)]
+    pub min_length_seconds: Option<String>,
+    #[field(name = "maxLengthSeconds")]
+    #[param(value_type = Option<usize>)]
+    pub max_length_seconds: Option<String>,
+}
+
+impl PodcastFilterRequest {
+    pub fn validate(self, taxonomy: &Taxonomy) -> Result<SearchQuery, ApiError> {
+        Ok(validate_search_query(
+            self.query,
+            self.tags,
+            parse_param("minLengthSeconds", self.min_length_seconds)?,
+            parse_param("maxLengthSeconds", self.max_length_seconds)?,
+        )?
+        .with_any_tags(validate_category(self.category, taxonomy)?))
+    }
+}
+
+/// Query params made up of a `PodcastFilterRequest` along with params `T` that are specific to a route.
+///
+/// Rocket can't flatten one form into another, so every query field is given to both forms, which
+/// ignore fields that they don't have since query params are parsed leniently.
+pub struct FilteredRequest<T> {
+    pub filter: PodcastFilterRequest,
+    pub params: T,
+}
+
+#[rocket::async_trait]
+impl<'r, T: FromForm<'r>> FromForm<'r> for FilteredRequest<T> {
+    type Context = (
+        <PodcastFilterRequest as FromForm<'r>>::Context,
+        <T as FromForm<'r>>::Context,
+    );
+
+    fn init(opts: form::Options) -> Self::Context {
+        (PodcastFilterRequest::init(opts), T::init(opts))
+    }
+
+    fn push_value(ctxt: &mut Self::Context, field: form::ValueField<'r>) {
+        PodcastFilterRequest::push_value(&mut ctxt.0, field.clone());
+        T::push_value(&mut ctxt.1, field);
+    }
+
+    // Data fields only come from request bodies, and filters are only read from the query.
+    async fn push_data(ctxt: &mut Self::Context, field: form::DataField<'r, '_>) {
+        T::push_data(&mut ctxt.1, field).await;
+    }
+
+    fn finalize(ctxt: Self::Context) -> form::Result<'r, Self> {
+        match (PodcastFilterRequest::finalize(ctxt.0), T::finalize(ctxt.1)) {
+            (Ok(filter), Ok(params)) => Ok(Self { filter, params }),
+            (Err(errors), Ok(_)) | (Ok(_), Err(errors)) => Err(errors),
+            (Err(mut errors), Err(more_errors)) => {
+                errors.extend(more_errors);
+                Err(errors)
+            }
+        }
+    }
+}
+
+#[derive(FromForm, IntoParams)]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct SearchPodcastsRequest {
+    /// Maximum number of podcasts to return. Defaults to 20, and can be at most 1000.
+    #[param(value_type = Option<usize>)]
+    pub limit: Option<String>,
+    #[param(value_type = Option<usize>)]
+    pub offset: Option<String>,
+}
+
+#[derive(FromForm, IntoParams)]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct SearchTagsRequest {
+    /// Maximum number of tags to return. Returns all tags by default, and can be at most 1000.
+    #[param(value_type = Option<usize>)]
+    pub limit: Option<String>,
+    #[param(value_type = Option<usize>)]
+    pub offset: Option<String>,
+    /// Only return tags that contain this string (case-insensitive).
+    pub filter: Option<String>,
+}
+
+#[derive(FromForm, IntoParams)]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct OpmlRequest {
+    /// Comma-separated list of tags to include. Takes precedence over `top`.
+    pub tags: Option<String>,
+    /// Only include this many of the most used tags.
+    #[param(value_type = Option<usize>)]
+    pub top: Option<String>,
+}
+
+#[derive(FromForm, IntoParams)]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct TimelineRequest {
+    /// Either `year` (the default) or `month`.
+    pub period: Option<String>,
+}
+
+#[derive(FromForm, IntoParams)]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct TagTrendsRequest {
+    /// Either `year` (the default) or `month`.
+    pub period: Option<String>,
+    /// Number of most used tags to include. Defaults to 10, and can be at most 100.
+    #[param(value_type = Option<usize>)]
+    pub top: Option<String>,
+}
+
+#[derive(FromForm, IntoParams)]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct LengthHistogramRequest {
+    /// Width of each bucket in seconds. Defaults to 600, and must be between 60 and 86400.
+    #[field(name = "bucketSeconds")]
+    #[param(value_type = Option<u64>)]
+    pub bucket_seconds: Option<String>,
+}
+
+#[derive(FromForm, IntoParams)]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct TagGraphRequest {
+    /// Only include tags that appear on at least this many podcasts. Defaults to 1.
+    #[field(name = "minCount")]
+    #[param(value_type = Option<usize>)]
+    pub min_count: Option<String>,
+    /// Only include edges between tags that appear together on at least this many podcasts. Defaults to 1.
+    #[field(name = "minWeight")]
+    #[param(value_type = Option<usize>)]
+    pub min_weight: Option<String>,
+    /// Maximum number of tags to include, keeping the most used ones. Defaults to 50, and can be at most 500.
+    #[field(name = "maxNodes")]
+    #[param(value_type = Option<usize>)]
+    pub max_nodes: Option<String>,
+}
+
+#[derive(FromForm, IntoParams)]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub struct TagDirectoryRequest {
+    /// Either `popularity` (the default), which lists the most used tags first, or `name`.
+    pub order: Option<String>,
+    /// Only list tags that start with this string (case-insensitive).
+    pub prefix: Option<String>,
+    /// Maximum number of tags to return. Defaults to 100, and can be at most 1000.
+    #[param(value_type = Option<usize>)]
+    pub limit: Option<String>,
+    #[param(value_type = Option<usize>)]
+    pub offset: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TagCount {
+    #[schema(value_type = String)]
+    tag: PodcastTag,
+    count: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagCountsResponse {
+    tags: Vec<TagCount>,
+    /// Number of matching tags that were left out because of `limit` and `offset`.
+    remaining_tag_count: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagDirectoryResponse {
+    tags: Vec<TagSummary>,
+    /// Number of matching tags that were left out because of `limit` and `offset`.
+    remaining_tag_count: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EpisodeSummary {
+    #[schema(schema_with = podcast_number_schema)]
+    podcast_number: PodcastNumber,
+    title: String,
+    /// Unix timestamp (in seconds) of when the episode was published.
+    create_time: i64,
+}
+
+impl EpisodeSummary {
+    fn new(podcast: &Podcast) -> Self {
+        Self {
+            podcast_number: podcast.get_podcast_number().clone(),
+            title: podcast.get_title().to_string(),
+            create_time: podcast.get_create_time(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagDetailsResponse {
+    #[schema(value_type = String)]
+    tag: PodcastTag,
+    episode_count: usize,
+    /// Unix timestamp (in seconds) of when the first podcast with the tag was published.
+    first_episode_time: i64,
+    /// Unix timestamp (in seconds) of when the latest podcast with the tag was published.
+    last_episode_time: i64,
+    total_length_seconds: u64,
+    /// Tags that most often appear on the same podcasts as this one.
+    related_tags: Vec<TagCount>,
+    /// Latest podcasts with the tag, newest first.
+    recent_episodes: Vec<EpisodeSummary>,
+    /// Stable URL of the RSS feed containing all podcasts with the tag.
+    rss_feed_url: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncResult {
+    /// RFC 3339 timestamp of when the sync finished.
+    time: String,
+    successful: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusResponse {
+    /// Short hash of the podcast catalogue, which changes whenever any podcast does.
+    catalogue_version: String,
+    podcast_count: usize,
+    /// Number of distinct tags across all podcasts.
+    tag_count: usize,
+    newest_episode: Option<EpisodeSummary>,
+    oldest_episode: Option<EpisodeSummary>,
+    total_audio_hours: f64,
+    last_sync: Option<SyncResult>,
+    /// RFC 3339 timestamp of the last sync that succeeded.
+    last_successful_sync_time: Option<String>,
+    /// Either `prod` or `mock`.
+    server_mode: &'static str,
+    /// Server version, followed by the commit it was built from if known.
+    build_version: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TimelineResponse {
+    period: Period,
+    periods: Vec<PeriodStats>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagTrendsResponse {
+    period: Period,
+    #[serde(flatten)]
+    trends: TagTrends,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LengthHistogramResponse {
+    bucket_seconds: u64,
+    buckets: Vec<LengthBucket>,
+}
+
+/// Clears the search index and search cache.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Search index was reset"),
+        (status = 503, description = "Search backend failed", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+// TODO - Guard this handler with an API key or something
+#[post("/reset")]
+#[tracing::instrument(skip_all, fields(request_id = %request_id))]
+pub async fn reset_handler(
+    request_id: RequestId,
+    search_backend: &State<SearchBackend>,
+) -> Result<(), ApiError> {
+    search_backend
+        .reset()
+        .await
+        .map_err(ApiError::search_backend)
+}
+
+#[utoipa::path(
+    params(("podcast_num" = String, Path, description = "Number of the podcast episode, or ID of a special episode")),
+    responses(
+        (status = 200, description = "The podcast", body = Podcast),
+        (status = 404, description = "Podcast does not exist", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/podcasts/<podcast_num>")]
+pub fn get_podcast_handler(
+    podcast_num: String,
+    fdr_cache: &State<FdrCache>,
+) -> Result<Podcast, ApiError> {
+    get_cached_podcast(&podcast_num, fdr_cache)
+}
+
+/// Chapters of the podcast in the Podcasting 2.0 JSON chapters format, which
+/// is empty if its description doesn't list any.
+#[utoipa::path(
+    params(("podcast_num" = String, Path, description = "Number of the podcast episode, or ID of a special episode")),
+    responses(
+        (status = 200, description = "The podcast's chapters", body = ChaptersDocument, content_type = "application/json+chapters"),
+        (status = 404, description = "Podcast does not exist", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/podcasts/<podcast_num>/chapters")]
+pub fn get_podcast_chapters_handler(
+    podcast_num: String,
+    fdr_cache: &State<FdrCache>,
+) -> Result<ChaptersDocument, ApiError> {
+    let podcast = get_cached_podcast(&podcast_num, fdr_cache)?;
+    Ok(ChaptersDocument::new(podcast.get_chapters().to_vec()))
+}
+
+/// Transcript of the podcast, converted to the requested format. Subtitle
+/// formats are only available if the transcript has timings.
+#[utoipa::path(
+    params(("podcast_num" = String, Path, description = "Number of the podcast episode, or ID of a special episode"), ("format" = Option<TranscriptFormat>, Query, description = "Format to return the transcript in, which defaults to `text`. The podcast's `transcriptFormats` field lists the formats it's available in.")),
+    responses(
+        (status = 200, description = "The podcast's transcript", body = String, content_type = "text/plain"),
+        (status = 400, description = "Invalid parameters", body = ApiError, content_type = "application/problem+json"),
+        (status = 404, description = "Podcast does not exist or has no transcript in the format", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/podcasts/<podcast_num>/transcript?<format>")]
+pub fn get_podcast_transcript_handler(
+    podcast_num: String,
+    format: Option<String>,
+    fdr_cache: &State<FdrCache>,
+    transcript_store: &State<TranscriptStore>,
+) -> Result<TranscriptDocument, ApiError> {
+    let format = parse_param("format", format)?.unwrap_or(TranscriptFormat::Text);
+    let podcast = get_cached_podcast(&podcast_num, fdr_cache)?;
+    transcript_store
+        .get(podcast.get_podcast_number())
+        .and_then(|transcript| transcript.render(format))
+        .map(|contents| TranscriptDocument::new(contents, format))
+        .ok_or_else(|| {
+            ApiError::not_found(
+                ApiErrorCode::TranscriptNotFound,
+                format!(
+                    "Podcast {} has no transcript in the {} format.",
+                    podcast_num, format
+                ),
+            )
+        })
+}
+
+/// Audio of the podcast, proxied through the server so that it doesn't depend
+/// on the availability of the audio host once it's cached. Supports byte-range
+/// requests, so players can seek without downloading the whole file.
+#[utoipa::path(
+    params(("podcast_num" = String, Path, description = "Number of the podcast episode, or ID of a special episode")),
+    responses(
+        (status = 200, description = "The podcast's audio"),
+        (status = 206, description = "The range of the podcast's audio requested by the `Range` header"),
+        (status = 404, description = "Podcast does not exist, or the audio proxy is disabled", body = ApiError, content_type = "application/problem+json"),
+        (status = 416, description = "The `Range` header doesn't overlap the audio"),
+        (status = 502, description = "Audio host failed", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/podcasts/<podcast_num>/audio")]
+#[tracing::instrument(skip_all, fields(request_id = %request_id))]
+pub async fn get_podcast_audio_handler(
+    podcast_num: String,
+    range_header: RangeHeader,
+    request_id: RequestId,
+    fdr_cache: &State<FdrCache>,
+    audio_cache: &State<Option<AudioCache>>,
+) -> Result<AudioResponse, ApiError> {
+    let audio_cache = match audio_cache.inner() {
+        Some(audio_cache) => audio_cache,
+        None => return Err(ApiError::audio_proxy_disabled()),
+    };
+    let podcast = get_cached_podcast(&podcast_num, fdr_cache)?;
+    match audio_cache.open(podcast.get_audio_link()).await {
+        Ok(audio) => Ok(AudioResponse::new(
+            audio,
+            range_header,
+            podcast.get_audio_mime_type().to_string(),
+        )),
+        Err(error) => {
+            tracing::warn!(error = %error, "Failed to fetch podcast audio");
+            Err(ApiError::new(
+                rocket::http::Status::BadGateway,
+                ApiErrorCode::AudioUnavailable,
+                format!(
+                    "Audio for podcast {} is currently unavailable: {}",
+                    podcast_num, error
+                ),
+            ))
+        }
+    }
+}
+
+/// Size of the audio cache and how effective it's been.
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Audio cache stats", body = AudioCacheStats),
+        (status = 404, description = "The audio proxy is disabled", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/audio/cache")]
+pub fn get_audio_cache_stats_handler(
+    audio_cache: &State<Option<AudioCache>>,
+) -> Result<content::Json<String>, ApiError> {
+    match audio_cache.inner() {
+        Some(audio_cache) => Ok(content::Json(
+            serde_json::json!(audio_cache.get_stats()).to_string(),
+        )),
+        None => Err(ApiError::audio_proxy_disabled()),
+    }
+}
+
+fn get_cached_podcast(podcast_num: &str, fdr_cache: &FdrCache) -> Result<Podcast, ApiError> {
+    let podcast_or = match podcast_num.parse::<PodcastNumber>() {
+        Ok(num) => fdr_cache.get_podcast(&num).cloned(),
+        Err(_) => None,
+    };
+
+    podcast_or.ok_or_else(|| {
+        ApiError::not_found(
+            ApiErrorCode::PodcastNotFound,
+            format!("Podcast {} does not exist.", podcast_num),
+        )
+    })
+}
+
+#[utoipa::path(
+    params(PodcastFilterRequest, SearchPodcastsRequest),
+    responses(
+        (status = 200, description = "Podcasts matching the search", body = SearchResult),
+        (status = 400, description = "Invalid parameters", body = ApiError, content_type = "application/problem+json"),
+        (status = 503, description = "Search backend failed", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/search/podcasts?<request..>")]
+#[tracing::instrument(skip_all, fields(request_id = %request_id))]
+pub async fn search_podcasts_handler(
+    request: FilteredRequest<SearchPodcastsRequest>,
+    request_id: RequestId,
+    search_backend: &State<SearchBackend>,
+    taxonomy: &State<Taxonomy>,
+) -> Result<SearchResult, ApiError> {
+    search_podcasts(request, DEFAULT_SEARCH_LIMIT, search_backend, taxonomy).await
+}
+
+/// Searches for podcasts, returning `default_limit` hits if the request doesn't set a limit.
+pub async fn search_podcasts(
+    request: FilteredRequest<SearchPodcastsRequest>,
+    default_limit: usize,
+    search_backend: &SearchBackend,
+    taxonomy: &Taxonomy,
+) -> Result<SearchResult, ApiError> {
+    let (limit, offset) = validate_pagination(
+        parse_param("limit", request.params.limit)?,
+        parse_param("offset", request.params.offset)?,
+        Some(default_limit),
+    )?;
+    let search_query = request.filter.validate(taxonomy)?;
+
+    search_backend
+        .search(&search_query, limit.unwrap_or(default_limit), offset)
+        .await
+        .map_err(ApiError::search_backend)
+}
+
+/// Generates an RSS feed containing all podcasts that match the search.
+#[utoipa::path(
+    params(PodcastFilterRequest, ("media" = Option<String>, Query, description = "Type of media to link each podcast to, either `audio` (the default) or `video`. Podcasts without that type of media link to their audio.")),
+    responses(
+        (status = 200, description = "RSS feed", body = String, content_type = "application/xml"),
+        (status = 400, description = "Invalid parameters", body = ApiError, content_type = "application/problem+json"),
+        (status = 503, description = "Search backend failed", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/search/podcasts/rss?<media>&<request..>")]
+#[tracing::instrument(skip_all, fields(request_id = %request_id))]
+pub async fn search_podcasts_as_rss_feed_handler(
+    media: Option<String>,
+    request: PodcastFilterRequest,
+    request_id: RequestId,
+    fdr_cache: &State<FdrCache>,
+    search_backend: &State<SearchBackend>,
+    taxonomy: &State<Taxonomy>,
+    env_vars: &State<EnvironmentVariables>,
+) -> Result<RssFeed, ApiError> {
+    let media_type = parse_param("media", media)?.unwrap_or(MediaType::Audio);
+    generate_custom_rss_feed(
+        &request.validate(taxonomy)?,
+        fdr_cache,
+        search_backend,
+        media_type,
+        env_vars.get_public_base_url(),
+    )
+    .await
+}
+
+/// Counts how many podcasts matching the search have each tag, most used tags first.
+#[utoipa::path(
+    params(PodcastFilterRequest, SearchTagsRequest),
+    responses(
+        (status = 200, description = "Tags with counts", body = TagCountsResponse),
+        (status = 400, description = "Invalid parameters", body = ApiError, content_type = "application/problem+json"),
+        (status = 503, description = "Search backend failed", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/search/tags?<request..>")]
+#[tracing::instrument(skip_all, fields(request_id = %request_id))]
+pub async fn search_tags_handler(
+    request: FilteredRequest<SearchTagsRequest>,
+    request_id: RequestId,
+    fdr_cache: &State<FdrCache>,
+    search_backend: &State<SearchBackend>,
+    tag_normalizer: &State<TagNormalizer>,
+    taxonomy: &State<Taxonomy>,
+) -> Result<content::Json<String>, ApiError> {
+    let (limit, offset) = validate_pagination(
+        parse_param("limit", request.params.limit)?,
+        parse_param("offset", request.params.offset)?,
+        None,
+    )?;
+    let filter = validate_text("filter", request.params.filter)?;
+    let search_query = request.filter.validate(taxonomy)?;
+
+    let podcasts = get_matching_podcasts(&search_query, fdr_cache, search_backend).await?;
+
+    let mut counts_by_tag = HashMap::new();
+    for podcast in &podcasts {
+        for tag in podcast.get_tags() {
+            match counts_by_tag.get_mut(tag) {
+                Some(count) => {
+                    *count += 1;
+                }
+                None => {
+                    counts_by_tag.insert(tag.clone(), 1);
+                }
+            };
+        }
+    }
+
+    // Delete tags that have already been selected. They're normalized like
+    // they are when searching, so that a tag selected by an alias is removed too.
+    for tag in search_query.normalize_tags(tag_normalizer).get_tags() {
+        counts_by_tag.remove(tag);
+    }
+
+    let mut counts_list: Vec<(PodcastTag, usize)> = counts_by_tag.into_iter().collect();
+    if let Some(filter) = filter {
+        counts_list.retain(|(tag, _)| {
+            tag.to_string()
+                .to_lowercase()
+                .contains(&filter.to_lowercase())
+        });
+    }
+    counts_list.sort_by(|(tag_one, count_one), (tag_two, count_two)| {
+        let count_ordering = count_two.cmp(count_one);
+        if count_ordering == std::cmp::Ordering::Equal {
+            tag_one.get_key().cmp(tag_two.get_key())
+        } else {
+            count_ordering
+        }
+    });
+    let pretrimmed_tag_count = counts_list.len();
+    // Over-draining causes a panic.
+    // Here we're making sure that we
+    // don't drain more items than
+    // exist in the array.
+    counts_list.drain(0..offset.min(counts_list.len()));
+    if let Some(limit) = limit {
+        counts_list.truncate(limit);
+    }
+    let trimmed_tag_count = counts_list.len();
+
+    let response = TagCountsResponse {
+        tags: counts_list
+            .into_iter()
+            .map(|(tag, count)| TagCount { tag, count })
+            .collect(),
+        remaining_tag_count: pretrimmed_tag_count - trimmed_tag_count,
+    };
+
+    Ok(content::Json(serde_json::json!(response).to_string()))
+}
+
+/// Lists every tag in the catalogue along with how often and when it was used.
+/// Unlike `/search/tags`, this doesn't run a search, so it can't be narrowed down by podcast.
+#[utoipa::path(
+    params(TagDirectoryRequest),
+    responses(
+        (status = 200, description = "Tag directory", body = TagDirectoryResponse),
+        (status = 400, description = "Invalid parameters", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/tags?<request..>")]
+pub fn get_tag_directory_handler(
+    request: TagDirectoryRequest,
+    fdr_cache: &State<FdrCache>,
+) -> Result<content::Json<String>, ApiError> {
+    let order = parse_param("order", request.order)?.unwrap_or(TagOrder::Popularity);
+    // Tags are normalized, so the prefix must be too.
+    let prefix = normalize_text(&validate_text("prefix", request.prefix)?.unwrap_or_default());
+    let (limit, offset) = validate_pagination(
+        parse_param("limit", request.limit)?,
+        parse_param("offset", request.offset)?,
+        Some(DEFAULT_TAG_DIRECTORY_LIMIT),
+    )?;
+
+    let tag_index = fdr_cache.get_tag_index();
+    let summaries = tag_index.list(order, &prefix);
+    let page: Vec<TagSummary> = summaries
+        .iter()
+        .skip(offset)
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|summary| (*summary).clone())
+        .collect();
+    let response = TagDirectoryResponse {
+        remaining_tag_count: summaries.len() - page.len(),
+        tags: page,
+    };
+    Ok(content::Json(serde_json::json!(response).to_string()))
+}
+
+// Resolves a tag from a URL to its canonical form, along with all podcasts
+// that have it. Fails if no podcasts do.
+fn get_podcasts_with_tag(
+    raw_tag: &str,
+    fdr_cache: &FdrCache,
+    tag_normalizer: &TagNormalizer,
+) -> Result<(PodcastTag, Vec<Podcast>), ApiError> {
+    let tag = tag_normalizer.normalize(raw_tag);
+    let podcasts = fdr_cache.get_podcasts_with_tag(&tag);
+    if podcasts.is_empty() {
+        return Err(ApiError::not_found(
+            ApiErrorCode::TagNotFound,
+            format!("No podcasts have the tag '{}'.", raw_tag),
+        ));
+    }
+    // The tag is returned the way that the podcasts write it, rather than the way the URL does.
+    let tag = podcasts[0].get_tags().get(&tag).unwrap().clone();
+    Ok((tag, podcasts))
+}
+
+/// Everything needed to render a page about a single tag. The tag may be given
+/// in any form, including as an alias, and is returned in its canonical form.
+#[utoipa::path(
+    params(("tag" = String, Path, description = "The tag")),
+    responses(
+        (status = 200, description = "Details of the tag", body = TagDetailsResponse),
+        (status = 404, description = "No podcasts have the tag", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/tags/<tag>")]
+pub fn get_tag_handler(
+    tag: String,
+    fdr_cache: &State<FdrCache>,
+    tag_normalizer: &State<TagNormalizer>,
+    env_vars: &State<EnvironmentVariables>,
+) -> Result<content::Json<String>, ApiError> {
+    let (tag, podcasts) = get_podcasts_with_tag(&tag, fdr_cache, tag_normalizer)?;
+
+    let mut counts_by_tag: HashMap<&PodcastTag, usize> = HashMap::new();
+    for podcast in &podcasts {
+        for related_tag in podcast.get_tags() {
+            if related_tag != &tag {
+                *counts_by_tag.entry(related_tag).or_insert(0) += 1;
+            }
+        }
+    }
+    let mut related_tags: Vec<TagCount> = counts_by_tag
+        .into_iter()
+        .map(|(tag, count)| TagCount {
+            tag: tag.clone(),
+            count,
+        })
+        .collect();
+    related_tags.sort_by(|tag_count_one, tag_count_two| {
+        tag_count_two
+            .count
+            .cmp(&tag_count_one.count)
+            .then_with(|| tag_count_one.tag.get_key().cmp(tag_count_two.tag.get_key()))
+    });
+    related_tags.truncate(TAG_DETAILS_RELATED_TAG_COUNT);
+
+    // Podcasts are sorted newest first.
+    let response = TagDetailsResponse {
+        rss_feed_url: get_tag_rss_feed_url(env_vars.get_public_base_url(), &tag),
+        tag,
+        episode_count: podcasts.len(),
+        first_episode_time: podcasts[podcasts.len() - 1].get_create_time(),
+        last_episode_time: podcasts[0].get_create_time(),
+        total_length_seconds: podcasts
+            .iter()
+            .map(|podcast| podcast.get_length_in_seconds().max(0) as u64)
+            .sum(),
+        related_tags,
+        recent_episodes: podcasts
+            .iter()
+            .take(TAG_DETAILS_RECENT_EPISODE_COUNT)
+            .map(EpisodeSummary::new)
+            .collect(),
+    };
+    Ok(content::Json(serde_json::json!(response).to_string()))
+}
+
+/// RSS feed containing all podcasts with the tag. This is the canonical feed
+/// for a tag, and its URL won't change as search params do.
+#[utoipa::path(
+    params(("tag" = String, Path, description = "The tag"), ("media" = Option<String>, Query, description = "Type of media to link each podcast to, either `audio` (the default) or `video`. Podcasts without that type of media link to their audio.")),
+    responses(
+        (status = 200, description = "RSS feed", body = String, content_type = "application/xml"),
+        (status = 404, description = "No podcasts have the tag", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/tags/<tag>/rss?<media>")]
+pub fn get_tag_rss_feed_handler(
+    tag: String,
+    media: Option<String>,
+    fdr_cache: &State<FdrCache>,
+    tag_normalizer: &State<TagNormalizer>,
+    env_vars: &State<EnvironmentVariables>,
+) -> Result<RssFeed, ApiError> {
+    let media_type = parse_param("media", media)?.unwrap_or(MediaType::Audio);
+    let (tag, podcasts) = get_podcasts_with_tag(&tag, fdr_cache, tag_normalizer)?;
+    Ok(generate_rss_feed(
+        &podcasts,
+        &format!("Freedomain: {}", tag.to_string()),
+        &format!(
+            "A feed containing all Freedomain podcasts tagged '{}'.",
+            tag.to_string()
+        ),
+        media_type,
+        env_vars.get_public_base_url(),
+    ))
+}
+
+/// Every part of a series of podcasts, in order.
+#[utoipa::path(
+    params(("series_id" = String, Path, description = "ID of the series, as given in its podcasts' `series` field")),
+    responses(
+        (status = 200, description = "The series", body = Series),
+        (status = 404, description = "Series does not exist", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/series/<series_id>")]
+pub fn get_series_handler(
+    series_id: String,
+    fdr_cache: &State<FdrCache>,
+) -> Result<content::Json<String>, ApiError> {
+    match fdr_cache.get_series_index().get_series(&series_id) {
+        Some(series) => Ok(content::Json(serde_json::json!(series).to_string())),
+        None => Err(ApiError::not_found(
+            ApiErrorCode::SeriesNotFound,
+            format!("Series '{}' does not exist.", series_id),
+        )),
+    }
+}
+
+/// Saves a search as a feed with a short, stable URL.
+#[utoipa::path(
+    params(PodcastFilterRequest),
+    responses(
+        (status = 201, description = "The saved feed", body = SavedFeedInfo),
+        (status = 400, description = "Invalid parameters", body = ApiError, content_type = "application/problem+json"),
+        (status = 500, description = "Feed could not be saved", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[post("/feeds?<request..>")]
+pub async fn create_saved_feed_handler(
+    request: PodcastFilterRequest,
+    saved_feed_store: &State<SavedFeedStore>,
+    taxonomy: &State<Taxonomy>,
+    tag_normalizer: &State<TagNormalizer>,
+) -> Result<status::Created<SavedFeedInfo>, ApiError> {
+    // Normalized so that searches for different forms of the same tags get the same ID.
+    let search_query = request.validate(taxonomy)?.normalize_tags(tag_normalizer);
+    let definition = FeedDefinition::from_search_query(&search_query);
+    match saved_feed_store.save(definition).await {
+        Ok(feed) => Ok(
+            status::Created::new(format!("/api/v1/feeds/{}", feed.get_id())).body(feed.into_info()),
+        ),
+        Err(err) => Err(ApiError::internal(format!("Failed to save feed: {}", err))),
+    }
+}
+
+/// Lists all saved feeds, oldest first.
+#[utoipa::path(responses((status = 200, description = "All saved feeds", body = SavedFeedList)))]
+#[get("/feeds")]
+pub fn list_saved_feeds_handler(saved_feed_store: &State<SavedFeedStore>) -> content::Json<String> {
+    let feed_list = SavedFeedList {
+        feeds: saved_feed_store
+            .list()
+            .into_iter()
+            .map(|feed| feed.into_info())
+            .collect(),
+    };
+    content::Json(serde_json::json!(feed_list).to_string())
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "The saved feed", body = SavedFeedInfo),
+        (status = 404, description = "Feed does not exist", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/feeds/<feed_id>")]
+pub fn get_saved_feed_handler(
+    feed_id: String,
+    saved_feed_store: &State<SavedFeedStore>,
+) -> Result<SavedFeedInfo, ApiError> {
+    match saved_feed_store.get(&feed_id) {
+        Some(feed) => Ok(feed.into_info()),
+        None => Err(ApiError::not_found(
+            ApiErrorCode::FeedNotFound,
+            format!("Feed '{}' does not exist.", feed_id),
+        )),
+    }
+}
+
+/// Generates the RSS feed for a saved feed.
+#[utoipa::path(
+    params(("media" = Option<String>, Query, description = "Type of media to link each podcast to, either `audio` (the default) or `video`. Podcasts without that type of media link to their audio.")),
+    responses(
+        (status = 200, description = "RSS feed", body = String, content_type = "application/xml"),
+        (status = 404, description = "Feed does not exist", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/feeds/<feed_id>/rss?<media>")]
+#[tracing::instrument(skip_all, fields(request_id = %request_id))]
+pub async fn get_saved_feed_as_rss_feed_handler(
+    feed_id: String,
+    media: Option<String>,
+    request_id: RequestId,
+    saved_feed_store: &State<SavedFeedStore>,
+    fdr_cache: &State<FdrCache>,
+    search_backend: &State<SearchBackend>,
+    env_vars: &State<EnvironmentVariables>,
+) -> Result<RssFeed, ApiError> {
+    let media_type = parse_param("media", media)?.unwrap_or(MediaType::Audio);
+    match saved_feed_store.get(&feed_id) {
+        Some(feed) => {
+            generate_custom_rss_feed(
+                &feed.get_definition().to_search_query(),
+                fdr_cache,
+                search_backend,
+                media_type,
+                env_vars.get_public_base_url(),
+            )
+            .await
+        }
+        None => Err(ApiError::not_found(
+            ApiErrorCode::FeedNotFound,
+            format!("Feed '{}' does not exist.", feed_id),
+        )),
+    }
+}
+
+/// Lists a feed per tag so that a whole topic library can be imported into a podcast app at once.
+/// Includes only the given tags if `tags` is set, otherwise the `top` most used tags (or all tags).
+#[utoipa::path(
+    params(OpmlRequest),
+    responses(
+        (status = 200, description = "OPML subscription list", body = String, content_type = "text/x-opml"),
+        (status = 400, description = "Invalid parameters", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/opml?<request..>")]
+pub fn get_tag_feeds_as_opml_handler(
+    request: OpmlRequest,
+    fdr_cache: &State<FdrCache>,
+    tag_normalizer: &State<TagNormalizer>,
+    env_vars: &State<EnvironmentVariables>,
+) -> Result<Opml, ApiError> {
+    let top = parse_param("top", request.top)?;
+    let selected_tags: Vec<PodcastTag> = match request.tags.filter(|tags| !tags.trim().is_empty()) {
+        Some(tags) => {
+            let search_query = validate_search_query(None, Some(tags), None, None)?;
+            let mut selected_tags: Vec<PodcastTag> = Vec::new();
+            // Aliases of the same tag would otherwise each get their own copy of its feed.
+            for tag in search_query.normalize_tags(tag_normalizer).get_tags() {
+                if !selected_tags.contains(tag) {
+                    selected_tags.push(tag.clone());
+                }
+            }
+            selected_tags
+        }
+        None => {
+            let mut counts_list = fdr_cache.get_tag_counts();
+            if let Some(top) = top {
+                counts_list.truncate(top);
+            }
+            counts_list.into_iter().map(|(tag, _)| tag).collect()
+        }
+    };
+
+    Ok(Opml::new(
+        "Freedomain Podcasts by Tag".to_string(),
+        selected_tags
+            .iter()
+            .map(|tag| {
+                OpmlOutline::new_rss(
+                    format!("Freedomain: {}", tag.to_string()),
+                    get_tag_rss_feed_url(env_vars.get_public_base_url(), tag),
+                )
+            })
+            .collect(),
+    ))
+}
+
+/// Number of episodes and their total length in each period, including empty periods.
+#[utoipa::path(
+    params(PodcastFilterRequest, TimelineRequest),
+    responses(
+        (status = 200, description = "Episode timeline", body = TimelineResponse),
+        (status = 400, description = "Invalid parameters", body = ApiError, content_type = "application/problem+json"),
+        (status = 503, description = "Search backend failed", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/analytics/timeline?<request..>")]
+#[tracing::instrument(skip_all, fields(request_id = %request_id))]
+pub async fn get_timeline_handler(
+    request: FilteredRequest<TimelineRequest>,
+    request_id: RequestId,
+    fdr_cache: &State<FdrCache>,
+    search_backend: &State<SearchBackend>,
+    taxonomy: &State<Taxonomy>,
+) -> Result<content::Json<String>, ApiError> {
+    let period = parse_param("period", request.params.period)?.unwrap_or(Period::Year);
+    let search_query = request.filter.validate(taxonomy)?;
+
+    let podcasts = get_matching_podcasts(&search_query, fdr_cache, search_backend).await?;
+    let podcast_refs: Vec<&Podcast> = podcasts.iter().collect();
+    let response = TimelineResponse {
+        period,
+        periods: analytics::get_timeline(&podcast_refs, period),
+    };
+    Ok(content::Json(serde_json::json!(response).to_string()))
+}
+
+/// How often the most used tags appear in each period.
+#[utoipa::path(
+    params(PodcastFilterRequest, TagTrendsRequest),
+    responses(
+        (status = 200, description = "Tag usage over time", body = TagTrendsResponse),
+        (status = 400, description = "Invalid parameters", body = ApiError, content_type = "application/problem+json"),
+        (status = 503, description = "Search backend failed", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/analytics/tags/trends?<request..>")]
+#[tracing::instrument(skip_all, fields(request_id = %request_id))]
+pub async fn get_tag_trends_handler(
+    request: FilteredRequest<TagTrendsRequest>,
+    request_id: RequestId,
+    fdr_cache: &State<FdrCache>,
+    search_backend: &State<SearchBackend>,
+    taxonomy: &State<Taxonomy>,
+) -> Result<content::Json<String>, ApiError> {
+    let period = parse_param("period", request.params.period)?.unwrap_or(Period::Year);
+    let top = validate_range(
+        "top",
+        parse_param("top", request.params.top)?,
+        DEFAULT_TAG_TREND_COUNT,
+        1,
+        MAX_TAG_TREND_COUNT,
+    )?;
+    let search_query = request.filter.validate(taxonomy)?;
+
+    let podcasts = get_matching_podcasts(&search_query, fdr_cache, search_backend).await?;
+    let podcast_refs: Vec<&Podcast> = podcasts.iter().collect();
+    let response = TagTrendsResponse {
+        period,
+        trends: analytics::get_tag_trends(&podcast_refs, period, top),
+    };
+    Ok(content::Json(serde_json::json!(response).to_string()))
+}
+
+/// Number of episodes in each range of lengths.
+#[utoipa::path(
+    params(PodcastFilterRequest, LengthHistogramRequest),
+    responses(
+        (status = 200, description = "Episode length histogram", body = LengthHistogramResponse),
+        (status = 400, description = "Invalid parameters", body = ApiError, content_type = "application/problem+json"),
+        (status = 503, description = "Search backend failed", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/analytics/lengths?<request..>")]
+#[tracing::instrument(skip_all, fields(request_id = %request_id))]
+pub async fn get_length_histogram_handler(
+    request: FilteredRequest<LengthHistogramRequest>,
+    request_id: RequestId,
+    fdr_cache: &State<FdrCache>,
+    search_backend: &State<SearchBackend>,
+    taxonomy: &State<Taxonomy>,
+) -> Result<content::Json<String>, ApiError> {
+    let bucket_seconds = validate_range(
+        "bucketSeconds",
+        parse_param("bucketSeconds", request.params.bucket_seconds)?,
+        DEFAULT_HISTOGRAM_BUCKET_SECONDS,
+        MIN_HISTOGRAM_BUCKET_SECONDS,
+        24 * 60 * 60,
+    )?;
+    let search_query = request.filter.validate(taxonomy)?;
+
+    let podcasts = get_matching_podcasts(&search_query, fdr_cache, search_backend).await?;
+    let podcast_refs: Vec<&Podcast> = podcasts.iter().collect();
+    let response = LengthHistogramResponse {
+        bucket_seconds,
+        buckets: analytics::get_length_histogram(&podcast_refs, bucket_seconds),
+    };
+    Ok(content::Json(serde_json::json!(response).to_string()))
+}
+
+/// Graph of which tags appear together on the podcasts matching the search.
+#[utoipa::path(
+    params(PodcastFilterRequest, TagGraphRequest),
+    responses(
+        (status = 200, description = "Tag co-occurrence graph", body = TagGraph),
+        (status = 400, description = "Invalid parameters", body = ApiError, content_type = "application/problem+json"),
+        (status = 503, description = "Search backend failed", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/analytics/tags/graph?<request..>")]
+#[tracing::instrument(skip_all, fields(request_id = %request_id))]
+pub async fn get_tag_graph_handler(
+    request: FilteredRequest<TagGraphRequest>,
+    request_id: RequestId,
+    fdr_cache: &State<FdrCache>,
+    search_backend: &State<SearchBackend>,
+    taxonomy: &State<Taxonomy>,
+) -> Result<content::Json<String>, ApiError> {
+    let min_count = parse_param("minCount", request.params.min_count)?.unwrap_or(1);
+    let min_weight = parse_param("minWeight", request.params.min_weight)?.unwrap_or(1);
+    let max_nodes = validate_range(
+        "maxNodes",
+        parse_param("maxNodes", request.params.max_nodes)?,
+        DEFAULT_TAG_GRAPH_NODE_COUNT,
+        1,
+        MAX_TAG_GRAPH_NODE_COUNT,
+    )?;
+    let search_query = request.filter.validate(taxonomy)?;
+
+    let podcasts = get_matching_podcasts(&search_query, fdr_cache, search_backend).await?;
+    let podcast_refs: Vec<&Podcast> = podcasts.iter().collect();
+    let graph = analytics::get_tag_graph(&podcast_refs, min_count, min_weight, max_nodes);
+    Ok(content::Json(serde_json::json!(graph).to_string()))
+}
+
+/// The taxonomy of tag categories, with how many podcasts matching the search fall under each category and tag.
+#[utoipa::path(
+    params(PodcastFilterRequest),
+    responses(
+        (status = 200, description = "Taxonomy with episode counts", body = TaxonomyHierarchy),
+        (status = 400, description = "Invalid parameters", body = ApiError, content_type = "application/problem+json"),
+        (status = 503, description = "Search backend failed", body = ApiError, content_type = "application/problem+json")
+    )
+)]
+#[get("/taxonomy?<request..>")]
+#[tracing::instrument(skip_all, fields(request_id = %request_id))]
+pub async fn get_taxonomy_handler(
+    request: PodcastFilterRequest,
+    request_id: RequestId,
+    fdr_cache: &State<FdrCache>,
+    search_backend: &State<SearchBackend>,
+    taxonomy: &State<Taxonomy>,
+) -> Result<content::Json<String>, ApiError> {
+    let search_query = request.validate(taxonomy)?;
+
+    let podcasts = get_matching_podcasts(&search_query, fdr_cache, search_backend).await?;
+    let podcast_refs: Vec<&Podcast> = podcasts.iter().collect();
+    let hierarchy = taxonomy.get_hierarchy(&podcast_refs);
+    Ok(content::Json(serde_json::json!(hierarchy).to_string()))
+}
+
+/// Statistics about the podcast catalogue and the state of the server.
+#[utoipa::path(responses((status = 200, description = "Server status", body = StatusResponse)))]
+#[get("/status")]
+pub fn get_status_handler(
+    fdr_cache: &State<FdrCache>,
+    sync_status: &State<SyncStatus>,
+    env_vars: &State<EnvironmentVariables>,
+) -> content::Json<String> {
+    let catalogue_stats = fdr_cache.get_catalogue_stats();
+
+    let last_sync = sync_status
+        .get_last_sync_time()
+        .map(|last_sync_time| SyncResult {
+            time: last_sync_time.to_rfc3339(),
+            successful: sync_status.get_last_sync_error().is_none(),
+            error: sync_status.get_last_sync_error(),
+        });
+
+    let build_version = match option_env!("BUILD_COMMIT") {
+        Some(build_commit) => format!("{}+{}", env!("CARGO_PKG_VERSION"), build_commit),
+        None => env!("CARGO_PKG_VERSION").to_string(),
+    };
+
+    let response = StatusResponse {
+        catalogue_version: catalogue_stats.catalogue_version.clone(),
+        podcast_count: catalogue_stats.podcast_count,
+        tag_count: catalogue_stats.tag_count,
+        newest_episode: catalogue_stats
+            .newest_podcast_or
+            .as_ref()
+            .map(EpisodeSummary::new),
+        oldest_episode: catalogue_stats
+            .oldest_podcast_or
+            .as_ref()
+            .map(EpisodeSummary::new),
+        // Rounded to one decimal place.
+        total_audio_hours: (catalogue_stats.total_length_in_seconds as f64 / 360.0).round() / 10.0,
+        last_sync,
+        last_successful_sync_time: sync_status
+            .get_last_successful_sync_time()
+            .map(|time| time.to_rfc3339()),
+        server_mode: env_vars.get_server_mode().as_str(),
+        build_version,
+    };
+
+    content::Json(serde_json::json!(response).to_string())
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        reset_handler,
+        get_podcast_handler,
+        get_podcast_chapters_handler,
+        get_podcast_transcript_handler,
+        get_podcast_audio_handler,
+        search_podcasts_handler,
+        search_podcasts_as_rss_feed_handler,
+        search_tags_handler,
+        get_tag_directory_handler,
+        get_tag_handler,
+        get_tag_rss_feed_handler,
+        get_series_handler,
+        create_saved_feed_handler,
+        list_saved_feeds_handler,
+        get_saved_feed_handler,
+        get_saved_feed_as_rss_feed_handler,
+        get_tag_feeds_as_opml_handler,
+        get_status_handler,
+        get_audio_cache_stats_handler,
+        get_timeline_handler,
+        get_tag_trends_handler,
+        get_length_histogram_handler,
+        get_tag_graph_handler,
+        get_taxonomy_handler
+    ]
+}
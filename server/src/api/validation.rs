@@ -0,0 +1,244 @@
+//! Validation of user-supplied request parameters.
+//!
+//! Everything here returns a `400` problem document describing the offending
+//! parameter rather than passing unreasonable values on to the search backend.
+
+use super::error::{ApiError, ApiErrorCode};
+use super::parse_tag_query_string;
+use crate::podcast::PodcastTag;
+use crate::search::{SearchQuery, MAX_SEARCH_LIMIT};
+use crate::taxonomy::Taxonomy;
+use std::str::FromStr;
+
+/// Number of results returned when a request doesn't specify a `limit`.
+pub const DEFAULT_SEARCH_LIMIT: usize = 20;
+pub const MAX_OFFSET: usize = 10_000;
+/// Maximum length (in characters) of free-text params such as `query` and `filter`.
+pub const MAX_QUERY_LENGTH: usize = 256;
+pub const MAX_TAG_COUNT: usize = 20;
+pub const MAX_TAG_LENGTH: usize = 100;
+pub const DEFAULT_TAG_DIRECTORY_LIMIT: usize = 100;
+pub const DEFAULT_TAG_TREND_COUNT: usize = 10;
+pub const MAX_TAG_TREND_COUNT: usize = 100;
+pub const DEFAULT_TAG_GRAPH_NODE_COUNT: usize = 50;
+pub const MAX_TAG_GRAPH_NODE_COUNT: usize = 500;
+pub const DEFAULT_HISTOGRAM_BUCKET_SECONDS: u64 = 600;
+pub const MIN_HISTOGRAM_BUCKET_SECONDS: u64 = 60;
+
+/// Parses a raw query param. Rocket silently turns malformed optional params
+/// into `None`, so numeric params are received as strings and parsed here
+/// instead, allowing a malformed value to be reported.
+pub fn parse_param<T: FromStr>(name: &str, param: Option<String>) -> Result<Option<T>, ApiError> {
+    param
+        .map(|value| {
+            value.trim().parse().map_err(|_| {
+                ApiError::bad_request(
+                    ApiErrorCode::InvalidParameter,
+                    format!("Parameter '{}' has an invalid value.", name),
+                )
+            })
+        })
+        .transpose()
+}
+
+pub fn validate_text(name: &str, text: Option<String>) -> Result<Option<String>, ApiError> {
+    if let Some(text) = &text {
+        if text.chars().count() > MAX_QUERY_LENGTH {
+            return Err(ApiError::bad_request(
+                ApiErrorCode::InvalidParameter,
+                format!(
+                    "Parameter '{}' must be at most {} characters long.",
+                    name, MAX_QUERY_LENGTH
+                ),
+            ));
+        }
+    }
+    Ok(text)
+}
+
+pub fn validate_search_query(
+    query: Option<String>,
+    tags: Option<String>,
+    min_length_seconds: Option<usize>,
+    max_length_seconds: Option<usize>,
+) -> Result<SearchQuery, ApiError> {
+    let query = validate_text("query", query)?;
+
+    let tags = parse_tag_query_string(tags.filter(|tags| !tags.trim().is_empty()));
+    if tags.len() > MAX_TAG_COUNT {
+        return Err(ApiError::bad_request(
+            ApiErrorCode::InvalidParameter,
+            format!(
+                "At most {} tags can be searched for at once.",
+                MAX_TAG_COUNT
+            ),
+        ));
+    }
+    for tag in &tags {
+        if tag.to_string().is_empty() || tag.to_string().chars().count() > MAX_TAG_LENGTH {
+            return Err(ApiError::bad_request(
+                ApiErrorCode::InvalidParameter,
+                format!(
+                    "Tags must be between 1 and {} characters long.",
+                    MAX_TAG_LENGTH
+                ),
+            ));
+        }
+    }
+
+    if let (Some(min_length_seconds), Some(max_length_seconds)) =
+        (min_length_seconds, max_length_seconds)
+    {
+        if min_length_seconds > max_length_seconds {
+            return Err(ApiError::bad_request(
+                ApiErrorCode::InvalidParameter,
+                "Parameter 'minLengthSeconds' must not be greater than 'maxLengthSeconds'.",
+            ));
+        }
+    }
+
+    Ok(SearchQuery::new(
+        query,
+        tags,
+        min_length_seconds,
+        max_length_seconds,
+    ))
+}
+
+/// Resolves a taxonomy category to all of the tags under it, or to no tags
+/// if no category was given.
+pub fn validate_category(
+    category: Option<String>,
+    taxonomy: &Taxonomy,
+) -> Result<Vec<PodcastTag>, ApiError> {
+    match category.filter(|category| !category.is_empty()) {
+        Some(category) => match taxonomy.get_category_tags(&category) {
+            Some(tags) => Ok(tags.to_vec()),
+            None => Err(ApiError::bad_request(
+                ApiErrorCode::InvalidParameter,
+                format!("Category '{}' does not exist.", category),
+            )),
+        },
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Checks that a numeric param is within `min..=max`, falling back to `default` if it wasn't given.
+pub fn validate_range<T: PartialOrd + std::fmt::Display>(
+    name: &str,
+    value: Option<T>,
+    default: T,
+    min: T,
+    max: T,
+) -> Result<T, ApiError> {
+    let value = value.unwrap_or(default);
+    if value < min || value > max {
+        return Err(ApiError::bad_request(
+            ApiErrorCode::InvalidParameter,
+            format!("Parameter '{}' must be between {} and {}.", name, min, max),
+        ));
+    }
+    Ok(value)
+}
+
+/// Checks `limit` and `offset`, falling back to `default_limit` if no limit was requested.
+pub fn validate_pagination(
+    limit: Option<usize>,
+    offset: Option<usize>,
+    default_limit: Option<usize>,
+) -> Result<(Option<usize>, usize), ApiError> {
+    let limit = limit.or(default_limit);
+    if let Some(limit) = limit {
+        if limit > MAX_SEARCH_LIMIT {
+            return Err(ApiError::bad_request(
+                ApiErrorCode::InvalidParameter,
+                format!("Parameter 'limit' must be at most {}.", MAX_SEARCH_LIMIT),
+            ));
+        }
+    }
+
+    let offset = offset.unwrap_or(0);
+    if offset > MAX_OFFSET {
+        return Err(ApiError::bad_request(
+            ApiErrorCode::InvalidParameter,
+            format!("Parameter 'offset' must be at most {}.", MAX_OFFSET),
+        ));
+    }
+
+    Ok((limit, offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tag_normalizer::TagNormalizer;
+
+    #[test]
+    fn test_validate_search_query() {
+        assert!(validate_search_query(None, None, None, None).is_ok());
+        assert!(validate_search_query(None, Some("".to_string()), None, None).is_ok());
+        assert!(validate_search_query(
+            Some("peaceful parenting".to_string()),
+            Some("foo,bar".to_string()),
+            Some(0),
+            Some(0)
+        )
+        .is_ok());
+
+        // Length range is inverted.
+        assert!(validate_search_query(None, None, Some(2), Some(1)).is_err());
+        // Query is too long.
+        assert!(
+            validate_search_query(Some("a".repeat(MAX_QUERY_LENGTH + 1)), None, None, None)
+                .is_err()
+        );
+        // Too many tags.
+        assert!(validate_search_query(
+            None,
+            Some(vec!["tag"; MAX_TAG_COUNT + 1].join(",")),
+            None,
+            None
+        )
+        .is_err());
+        // Empty tag.
+        assert!(validate_search_query(None, Some("foo,,bar".to_string()), None, None).is_err());
+    }
+
+    #[test]
+    fn test_validate_category() {
+        let taxonomy = Taxonomy::new_mock(&TagNormalizer::default());
+        assert!(validate_category(None, &taxonomy).unwrap().is_empty());
+        assert!(validate_category(Some("".to_string()), &taxonomy)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            validate_category(Some("Category #0".to_string()), &taxonomy)
+                .unwrap()
+                .len(),
+            20
+        );
+        assert!(validate_category(Some("Nonexistent".to_string()), &taxonomy).is_err());
+    }
+
+    #[test]
+    fn test_parse_param() {
+        assert_eq!(parse_param::<usize>("limit", None).unwrap(), None);
+        assert_eq!(
+            parse_param::<usize>("limit", Some("5".to_string())).unwrap(),
+            Some(5)
+        );
+        assert!(parse_param::<usize>("limit", Some("abc".to_string())).is_err());
+        assert!(parse_param::<usize>("limit", Some("-1".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_validate_pagination() {
+        assert_eq!(validate_pagination(None, None, None).unwrap(), (None, 0));
+        assert_eq!(
+            validate_pagination(None, Some(5), Some(DEFAULT_SEARCH_LIMIT)).unwrap(),
+            (Some(DEFAULT_SEARCH_LIMIT), 5)
+        );
+        assert!(validate_pagination(Some(MAX_SEARCH_LIMIT + 1), None, None).is_err());
+        assert!(validate_pagination(None, Some(MAX_OFFSET + 1), None).is_err());
+    }
+}
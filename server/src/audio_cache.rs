@@ -0,0 +1,779 @@
+//! Proxying of podcast audio through the server, backed by a cache on local disk.
+//!
+//! Audio is hosted by third parties, some of which are slow or intermittently
+//! unavailable. The first time a podcast's audio is requested it's downloaded
+//! to disk, and served from the partial file as it arrives. From then on it's
+//! served from disk, along with any byte ranges of it that players request
+//! when seeking. The cache has a size cap, and the least recently played files
+//! are evicted to stay under it.
+
+use rocket::http::{ContentType, Status};
+use rocket::request::{FromRequest, Outcome};
+use rocket::Request;
+use serde::Serialize;
+use sha2::Digest;
+use std::collections::HashMap;
+use std::error::Error;
+use std::io::{Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use utoipa::ToSchema;
+
+// Number of hex characters of the audio link hash used as a cached file's name.
+const CACHE_KEY_LENGTH: usize = 16;
+// Extension of files that are still being downloaded.
+const PARTIAL_FILE_EXTENSION: &str = "part";
+const UPSTREAM_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// Longest that the audio host can go without sending anything before its download is abandoned.
+const UPSTREAM_READ_TIMEOUT: Duration = Duration::from_secs(30);
+// Size of the chunks that a file that's still downloading is streamed to clients in.
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+// Sizes (in bytes) of cached files, keyed by file name.
+type AudioLru = lru::LruCache<String, u64>;
+// Progress of the downloads that are in progress, keyed by file name.
+type Downloads = HashMap<String, tokio::sync::watch::Receiver<DownloadProgress>>;
+
+#[derive(Clone, Debug)]
+enum DownloadProgress {
+    Connecting,
+    /// Writing to the partial file. The size is unknown if the audio host didn't send it.
+    Writing {
+        size_or: Option<u64>,
+    },
+    Finished {
+        size: u64,
+    },
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioCacheStats {
+    file_count: usize,
+    /// Total size of the cached files.
+    size_bytes: u64,
+    /// Size that the cache is kept under by evicting the least recently played files.
+    max_size_bytes: u64,
+    /// Number of requests served from a file that was already cached, since the server started.
+    hits: u64,
+    /// Number of requests that started a download of the file, since the server started.
+    misses: u64,
+    /// Number of requests that shared a download started by an earlier request, since the server started.
+    joined_downloads: u64,
+    /// Number of files evicted to make room for new ones, since the server started.
+    evictions: u64,
+    /// Number of downloads that failed, since the server started.
+    upstream_errors: u64,
+}
+
+struct AudioCacheState {
+    lru: AudioLru,
+    size_bytes: u64,
+    hits: u64,
+    misses: u64,
+    joined_downloads: u64,
+    evictions: u64,
+    upstream_errors: u64,
+}
+
+/// Podcast audio cached on local disk.
+#[derive(Clone)]
+pub struct AudioCache {
+    dir: PathBuf,
+    max_size_bytes: u64,
+    state: Arc<Mutex<AudioCacheState>>,
+    // Concurrent requests for a file that isn't cached share a single download.
+    // When both are locked, this is locked before `state`.
+    downloads: Arc<Mutex<Downloads>>,
+    http_client: reqwest::Client,
+    upstream_read_timeout: Duration,
+}
+
+impl AudioCache {
+    /// Uses the directory for the cache, creating it if needed. Files that were
+    /// cached before a restart are kept, in order of when they were downloaded.
+    pub fn new_with_dir(dir: &Path, max_size_bytes: u64) -> Result<Self, Box<dyn Error>> {
+        std::fs::create_dir_all(dir)?;
+
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            // Downloads that were interrupted by the restart can't be resumed.
+            if path.extension().and_then(|extension| extension.to_str())
+                == Some(PARTIAL_FILE_EXTENSION)
+            {
+                std::fs::remove_file(&path)?;
+                continue;
+            }
+            if let Some(file_name) = path.file_name().and_then(|file_name| file_name.to_str()) {
+                files.push((metadata.modified()?, file_name.to_string(), metadata.len()));
+            }
+        }
+        files.sort();
+
+        let mut state = AudioCacheState {
+            lru: AudioLru::unbounded(),
+            size_bytes: 0,
+            hits: 0,
+            misses: 0,
+            joined_downloads: 0,
+            evictions: 0,
+            upstream_errors: 0,
+        };
+        for (_, file_name, size) in files {
+            state.size_bytes += size;
+            state.lru.push(file_name, size);
+        }
+
+        let audio_cache = Self {
+            dir: dir.to_path_buf(),
+            max_size_bytes,
+            state: Arc::from(Mutex::from(state)),
+            downloads: Arc::from(Mutex::from(HashMap::new())),
+            http_client: reqwest::Client::builder()
+                .connect_timeout(UPSTREAM_CONNECT_TIMEOUT)
+                .build()?,
+            upstream_read_timeout: UPSTREAM_READ_TIMEOUT,
+        };
+        // The size cap may have been lowered since the files were cached.
+        let evicted_paths = audio_cache.evict_to_size_cap(&mut audio_cache.state.lock().unwrap());
+        remove_evicted_files(evicted_paths);
+        Ok(audio_cache)
+    }
+
+    pub fn get_stats(&self) -> AudioCacheStats {
+        let state = self.state.lock().unwrap();
+        AudioCacheStats {
+            file_count: state.lru.len(),
+            size_bytes: state.size_bytes,
+            max_size_bytes: self.max_size_bytes,
+            hits: state.hits,
+            misses: state.misses,
+            joined_downloads: state.joined_downloads,
+            evictions: state.evictions,
+            upstream_errors: state.upstream_errors,
+        }
+    }
+
+    /// Opens the cached audio at the link. If it isn't cached, it's downloaded
+    /// in the background and read from the partial file as it's written.
+    pub async fn open(
+        &self,
+        audio_link: &str,
+    ) -> Result<CachedAudio, Box<dyn Error + Send + Sync>> {
+        let file_name = get_cache_file_name(audio_link);
+        if let Some(cached_audio) = self.open_cached(&file_name) {
+            return Ok(cached_audio);
+        }
+
+        let (file, mut progress) = {
+            let mut downloads = self.downloads.lock().unwrap();
+            match downloads.get(&file_name) {
+                Some(progress) => {
+                    // The download may have just finished, and been moved to its final path.
+                    let file = std::fs::File::open(self.get_partial_path(&file_name))
+                        .or_else(|_| std::fs::File::open(self.dir.join(&file_name)))?;
+                    self.state.lock().unwrap().joined_downloads += 1;
+                    (file, progress.clone())
+                }
+                None => {
+                    // The download may have finished since the cache was checked.
+                    if let Some(cached_audio) = self.open_cached(&file_name) {
+                        return Ok(cached_audio);
+                    }
+                    let partial_file = std::fs::File::create(self.get_partial_path(&file_name))?;
+                    let file = std::fs::File::open(self.get_partial_path(&file_name))?;
+                    let (progress_sender, progress) =
+                        tokio::sync::watch::channel(DownloadProgress::Connecting);
+                    downloads.insert(file_name.clone(), progress.clone());
+                    self.state.lock().unwrap().misses += 1;
+
+                    // The download is its own task so that it finishes even if the client disconnects.
+                    let audio_cache = self.clone();
+                    let audio_link = audio_link.to_string();
+                    tokio::spawn(async move {
+                        audio_cache
+                            .download(file_name, audio_link, partial_file, progress_sender)
+                            .await;
+                    });
+                    (file, progress)
+                }
+            }
+        };
+
+        loop {
+            let current_progress = progress.borrow_and_update().clone();
+            match current_progress {
+                DownloadProgress::Writing {
+                    size_or: Some(size),
+                } => {
+                    return Ok(CachedAudio {
+                        file,
+                        size,
+                        progress_or: Some(progress),
+                    })
+                }
+                DownloadProgress::Finished { size } => {
+                    return Ok(CachedAudio {
+                        file,
+                        size,
+                        progress_or: None,
+                    })
+                }
+                DownloadProgress::Failed { error } => return Err(error.into()),
+                // Range requests can't be answered without knowing the size, so
+                // if the host doesn't send one the whole file is waited for.
+                DownloadProgress::Connecting | DownloadProgress::Writing { size_or: None } => {}
+            }
+            if progress.changed().await.is_err() {
+                return Err("Audio download stopped unexpectedly".into());
+            }
+        }
+    }
+
+    fn open_cached(&self, file_name: &str) -> Option<CachedAudio> {
+        let mut state = self.state.lock().unwrap();
+        let size = *state.lru.get(file_name)?;
+        match std::fs::File::open(self.dir.join(file_name)) {
+            Ok(file) => {
+                state.hits += 1;
+                Some(CachedAudio {
+                    file,
+                    size,
+                    progress_or: None,
+                })
+            }
+            // The file was deleted from outside of the server.
+            Err(_) => {
+                state.lru.pop(file_name);
+                state.size_bytes -= size;
+                None
+            }
+        }
+    }
+
+    // The file is written under a temporary name so that a failed download is
+    // never served to later requests.
+    fn get_partial_path(&self, file_name: &str) -> PathBuf {
+        self.dir
+            .join(format!("{}.{}", file_name, PARTIAL_FILE_EXTENSION))
+    }
+
+    // Downloads the audio to its partial file, then adds it to the cache.
+    async fn download(
+        &self,
+        file_name: String,
+        audio_link: String,
+        partial_file: std::fs::File,
+        progress_sender: tokio::sync::watch::Sender<DownloadProgress>,
+    ) {
+        tracing::info!(audio_link = %audio_link, "Downloading audio to cache");
+        let download_result = self
+            .write_download(&audio_link, partial_file, &progress_sender)
+            .await;
+
+        let download_result = match download_result {
+            Ok(size) => {
+                tokio::fs::rename(self.get_partial_path(&file_name), self.dir.join(&file_name))
+                    .await
+                    .map(|_| size)
+                    .map_err(|error| error.into())
+            }
+            Err(error) => Err(error),
+        };
+        match download_result {
+            Ok(size) => {
+                let evicted_paths = {
+                    let mut state = self.state.lock().unwrap();
+                    if let Some((_, replaced_size)) = state.lru.push(file_name.clone(), size) {
+                        state.size_bytes -= replaced_size;
+                    }
+                    state.size_bytes += size;
+                    self.evict_to_size_cap(&mut state)
+                };
+                tracing::info!(audio_link = %audio_link, size, "Cached audio");
+                let _ = progress_sender.send(DownloadProgress::Finished { size });
+                // The download stays registered until the file is in the cache, so
+                // that no request can find it in neither and download it again.
+                self.downloads.lock().unwrap().remove(&file_name);
+                let _ =
+                    tokio::task::spawn_blocking(move || remove_evicted_files(evicted_paths)).await;
+            }
+            Err(error) => {
+                tracing::warn!(audio_link = %audio_link, error = %error, "Failed to download audio");
+                let _ = tokio::fs::remove_file(self.get_partial_path(&file_name)).await;
+                self.state.lock().unwrap().upstream_errors += 1;
+                let _ = progress_sender.send(DownloadProgress::Failed {
+                    error: error.to_string(),
+                });
+                self.downloads.lock().unwrap().remove(&file_name);
+            }
+        }
+    }
+
+    // Writes the audio to the file, returning its size.
+    async fn write_download(
+        &self,
+        audio_link: &str,
+        file: std::fs::File,
+        progress_sender: &tokio::sync::watch::Sender<DownloadProgress>,
+    ) -> Result<u64, Box<dyn Error + Send + Sync>> {
+        let mut response = tokio::time::timeout(
+            self.upstream_read_timeout,
+            self.http_client.get(audio_link).send(),
+        )
+        .await??
+        .error_for_status()?;
+        let size_or = response.content_length();
+        let _ = progress_sender.send(DownloadProgress::Writing { size_or });
+
+        let mut file = tokio::fs::File::from_std(file);
+        let mut size = 0;
+        // Each chunk has its own timeout, so that a host that stalls doesn't
+        // hold up requests forever, but a slow one can still finish.
+        while let Some(chunk) =
+            tokio::time::timeout(self.upstream_read_timeout, response.chunk()).await??
+        {
+            file.write_all(&chunk).await?;
+            // Readers of the partial file are woken up once the chunk is flushed to it.
+            file.flush().await?;
+            size += chunk.len() as u64;
+            let _ = progress_sender.send(DownloadProgress::Writing { size_or });
+        }
+        if size_or.is_some_and(|expected_size| size != expected_size) {
+            return Err("Audio host closed the connection before sending the whole file".into());
+        }
+        Ok(size)
+    }
+
+    // Removes the least recently played files from the cache until it's under
+    // the size cap, returning the paths of the files to delete. They're deleted
+    // by the caller so that the state isn't locked while they are.
+    fn evict_to_size_cap(&self, state: &mut AudioCacheState) -> Vec<PathBuf> {
+        let mut evicted_paths = Vec::new();
+        while state.size_bytes > self.max_size_bytes {
+            let (file_name, size) = match state.lru.pop_lru() {
+                Some(entry) => entry,
+                None => break,
+            };
+            state.size_bytes -= size;
+            state.evictions += 1;
+            evicted_paths.push(self.dir.join(&file_name));
+        }
+        evicted_paths
+    }
+}
+
+fn remove_evicted_files(evicted_paths: Vec<PathBuf>) {
+    for path in evicted_paths {
+        if let Err(error) = std::fs::remove_file(&path) {
+            tracing::warn!(path = %path.display(), error = %error, "Failed to delete evicted audio file");
+        }
+    }
+}
+
+// Files are named after their link rather than their podcast, so that a
+// podcast whose audio link changes upstream isn't served stale audio.
+fn get_cache_file_name(audio_link: &str) -> String {
+    let mut hasher = sha2::Sha256::new();
+    hasher.update(audio_link);
+    let mut file_name = hex::encode(hasher.finalize());
+    file_name.truncate(CACHE_KEY_LENGTH);
+    file_name
+}
+
+/// A cached audio file, opened for reading.
+pub struct CachedAudio {
+    file: std::fs::File,
+    size: u64,
+    // Progress of the file's download, if it's still being downloaded.
+    progress_or: Option<tokio::sync::watch::Receiver<DownloadProgress>>,
+}
+
+// Streams bytes of a file that's still being downloaded, waiting for the
+// download whenever reading catches up with it.
+fn stream_download(
+    file: std::fs::File,
+    start: u64,
+    length: u64,
+    mut progress: tokio::sync::watch::Receiver<DownloadProgress>,
+) -> tokio::io::DuplexStream {
+    let (mut writer, reader) = tokio::io::duplex(STREAM_CHUNK_SIZE);
+    tokio::spawn(async move {
+        let mut file = tokio::fs::File::from_std(file);
+        if file.seek(SeekFrom::Start(start)).await.is_err() {
+            return;
+        }
+        let mut buffer = vec![0; STREAM_CHUNK_SIZE];
+        let mut remaining_length = length;
+        let mut download_ended = false;
+        while remaining_length > 0 {
+            let read_length = remaining_length.min(STREAM_CHUNK_SIZE as u64) as usize;
+            let read_length = match file.read(&mut buffer[..read_length]).await {
+                Ok(read_length) => read_length,
+                Err(_) => return,
+            };
+            if read_length == 0 {
+                // If the download failed, the response is cut short so that the client knows.
+                if download_ended {
+                    return;
+                }
+                download_ended = progress.changed().await.is_err();
+                continue;
+            }
+            // Writing only fails if the client disconnected.
+            if writer.write_all(&buffer[..read_length]).await.is_err() {
+                return;
+            }
+            remaining_length -= read_length as u64;
+        }
+    });
+    reader
+}
+
+/// Value of the request's `Range` header, if it has one.
+pub struct RangeHeader(Option<String>);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RangeHeader {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(RangeHeader(
+            request.headers().get_one("Range").map(str::to_string),
+        ))
+    }
+}
+
+/// Inclusive offsets of the bytes requested by a `Range` header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ByteRange {
+    start: u64,
+    end: u64,
+}
+
+enum RangeRequest {
+    Full,
+    Partial(ByteRange),
+    Unsatisfiable,
+}
+
+// Interprets a `Range` header for a file of the given size, as described in
+// RFC 7233. Headers that are malformed or request several ranges are ignored,
+// and the whole file is sent instead.
+fn parse_range_header(header: &str, size: u64) -> RangeRequest {
+    let range = match header.trim().strip_prefix("bytes=") {
+        Some(range) if !range.contains(',') => range.trim(),
+        _ => return RangeRequest::Full,
+    };
+    let (start, end) = match range.split_once('-') {
+        Some(bounds) => bounds,
+        None => return RangeRequest::Full,
+    };
+
+    // A range such as `-500` is a suffix, requesting the last 500 bytes.
+    if start.is_empty() {
+        return match end.parse::<u64>() {
+            Ok(suffix_length) if suffix_length > 0 && size > 0 => {
+                RangeRequest::Partial(ByteRange {
+                    start: size.saturating_sub(suffix_length),
+                    end: size - 1,
+                })
+            }
+            Ok(_) => RangeRequest::Unsatisfiable,
+            Err(_) => RangeRequest::Full,
+        };
+    }
+
+    let start = match start.parse::<u64>() {
+        Ok(start) => start,
+        Err(_) => return RangeRequest::Full,
+    };
+    let end = if end.is_empty() {
+        u64::MAX
+    } else {
+        match end.parse::<u64>() {
+            Ok(end) if end >= start => end,
+            _ => return RangeRequest::Full,
+        }
+    };
+    if start >= size {
+        return RangeRequest::Unsatisfiable;
+    }
+    RangeRequest::Partial(ByteRange {
+        start,
+        end: end.min(size - 1),
+    })
+}
+
+/// Cached audio, or the part of it requested by the `Range` header.
+pub struct AudioResponse {
+    audio: CachedAudio,
+    range_header: RangeHeader,
+    mime_type: String,
+}
+
+impl AudioResponse {
+    pub fn new(audio: CachedAudio, range_header: RangeHeader, mime_type: String) -> Self {
+        Self {
+            audio,
+            range_header,
+            mime_type,
+        }
+    }
+}
+
+impl<'r> rocket::response::Responder<'r, 'static> for AudioResponse {
+    fn respond_to(
+        self,
+        _request: &'r rocket::request::Request,
+    ) -> Result<rocket::response::Response<'static>, rocket::http::Status> {
+        let CachedAudio {
+            mut file,
+            size,
+            progress_or,
+        } = self.audio;
+        let range_request = match &self.range_header.0 {
+            Some(header) => parse_range_header(header, size),
+            None => RangeRequest::Full,
+        };
+
+        let mut response = rocket::Response::build();
+        response
+            .header(ContentType::parse_flexible(&self.mime_type).unwrap_or(ContentType::Binary))
+            .raw_header("Accept-Ranges", "bytes");
+        match range_request {
+            RangeRequest::Full => match progress_or {
+                Some(progress) => {
+                    response
+                        .raw_header("Content-Length", size.to_string())
+                        .streamed_body(stream_download(file, 0, size, progress));
+                }
+                None => {
+                    response.sized_body(size as usize, tokio::fs::File::from_std(file));
+                }
+            },
+            RangeRequest::Partial(range) => {
+                let length = range.end - range.start + 1;
+                // A sized body would send the rest of the file, so the length is set manually.
+                response
+                    .status(Status::PartialContent)
+                    .raw_header(
+                        "Content-Range",
+                        format!("bytes {}-{}/{}", range.start, range.end, size),
+                    )
+                    .raw_header("Content-Length", length.to_string());
+                match progress_or {
+                    Some(progress) => {
+                        response.streamed_body(stream_download(
+                            file,
+                            range.start,
+                            length,
+                            progress,
+                        ));
+                    }
+                    None => {
+                        file.seek(SeekFrom::Start(range.start))
+                            .map_err(|_| Status::InternalServerError)?;
+                        response.streamed_body(tokio::fs::File::from_std(file).take(length));
+                    }
+                }
+            }
+            RangeRequest::Unsatisfiable => {
+                response
+                    .status(Status::RangeNotSatisfiable)
+                    .raw_header("Content-Range", format!("bytes */{}", size));
+            }
+        }
+        response.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    fn create_test_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("audio-cache-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // Starts an audio host that serves a single request for a file of the
+    // given size. The file is sent in the chunks passed to the returned
+    // sender, and the connection is closed once the sender is dropped.
+    fn start_audio_host(size: usize) -> (String, std::sync::mpsc::Sender<Vec<u8>>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let audio_link = format!("http://{}/audio.mp3", listener.local_addr().unwrap());
+        let (chunk_sender, chunk_receiver) = std::sync::mpsc::channel::<Vec<u8>>();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"\r\n\r\n") {
+                let read_length = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read_length]);
+            }
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n",
+                size
+            );
+            for chunk in chunk_receiver {
+                let _ = stream.write_all(&chunk);
+            }
+        });
+        (audio_link, chunk_sender)
+    }
+
+    fn stream_cached_audio(audio: CachedAudio) -> tokio::io::DuplexStream {
+        stream_download(audio.file, 0, audio.size, audio.progress_or.unwrap())
+    }
+
+    async fn wait_for_downloads(audio_cache: &AudioCache) {
+        while !audio_cache.downloads.lock().unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[test]
+    fn test_parse_range_header() {
+        let parse = |header: &str| match parse_range_header(header, 1000) {
+            RangeRequest::Full => Some(None),
+            RangeRequest::Partial(range) => Some(Some((range.start, range.end))),
+            RangeRequest::Unsatisfiable => None,
+        };
+        assert_eq!(parse("bytes=0-"), Some(Some((0, 999))));
+        assert_eq!(parse("bytes=100-199"), Some(Some((100, 199))));
+        assert_eq!(parse("bytes=900-5000"), Some(Some((900, 999))));
+        assert_eq!(parse("bytes=-100"), Some(Some((900, 999))));
+        assert_eq!(parse("bytes=-5000"), Some(Some((0, 999))));
+        assert_eq!(parse("bytes=1000-"), None);
+        assert_eq!(parse("bytes=-0"), None);
+        assert_eq!(parse("bytes=0-1,5-6"), Some(None));
+        assert_eq!(parse("bytes=200-100"), Some(None));
+        assert_eq!(parse("items=0-1"), Some(None));
+    }
+
+    #[test]
+    fn test_evict_least_recently_played() {
+        let dir = create_test_dir();
+        std::fs::write(dir.join("interrupted.part"), [0; 10]).unwrap();
+        // Files with the same modification time are ordered by name.
+        for file_name in ["a", "b", "c"] {
+            std::fs::write(dir.join(file_name), [0; 10]).unwrap();
+        }
+
+        let audio_cache = AudioCache::new_with_dir(&dir, 25).unwrap();
+        assert!(!dir.join("interrupted.part").exists());
+        assert!(!dir.join("a").exists());
+        assert!(audio_cache.open_cached("b").is_some());
+        {
+            let mut state = audio_cache.state.lock().unwrap();
+            state.size_bytes += 10;
+            state.lru.push("d".to_string(), 10);
+            let evicted_paths = audio_cache.evict_to_size_cap(&mut state);
+            remove_evicted_files(evicted_paths);
+        }
+        // "b" was played more recently than "c", so "c" is evicted first.
+        assert!(dir.join("b").exists());
+        assert!(!dir.join("c").exists());
+
+        let stats = audio_cache.get_stats();
+        assert_eq!(stats.file_count, 2);
+        assert_eq!(stats.size_bytes, 20);
+        assert_eq!(stats.hits, 1);
+        assert_eq!(stats.evictions, 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stream_shared_download() {
+        let dir = create_test_dir();
+        let audio_cache = AudioCache::new_with_dir(&dir, 1000).unwrap();
+        let (audio_link, chunk_sender) = start_audio_host(10);
+
+        let mut first_stream = stream_cached_audio(audio_cache.open(&audio_link).await.unwrap());
+        chunk_sender.send(b"01234".to_vec()).unwrap();
+        // The start of the file is streamed before the rest of it is downloaded.
+        let mut start = [0; 5];
+        first_stream.read_exact(&mut start).await.unwrap();
+        assert_eq!(&start, b"01234");
+
+        // The audio host only accepts one connection, so this fails unless the download is shared.
+        let mut second_stream = stream_cached_audio(audio_cache.open(&audio_link).await.unwrap());
+        chunk_sender.send(b"56789".to_vec()).unwrap();
+        let mut rest = Vec::new();
+        first_stream.read_to_end(&mut rest).await.unwrap();
+        assert_eq!(rest, b"56789");
+        let mut whole = Vec::new();
+        second_stream.read_to_end(&mut whole).await.unwrap();
+        assert_eq!(whole, b"0123456789");
+
+        wait_for_downloads(&audio_cache).await;
+        let cached_audio = audio_cache.open(&audio_link).await.unwrap();
+        assert!(cached_audio.progress_or.is_none());
+        assert_eq!(cached_audio.size, 10);
+        let stats = audio_cache.get_stats();
+        assert_eq!(stats.file_count, 1);
+        assert_eq!(stats.misses, 1);
+        assert_eq!(stats.joined_downloads, 1);
+        assert_eq!(stats.hits, 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_download_cuts_response_short() {
+        let dir = create_test_dir();
+        let audio_cache = AudioCache::new_with_dir(&dir, 1000).unwrap();
+        let (audio_link, chunk_sender) = start_audio_host(10);
+
+        let mut stream = stream_cached_audio(audio_cache.open(&audio_link).await.unwrap());
+        chunk_sender.send(b"01234".to_vec()).unwrap();
+        drop(chunk_sender);
+        let mut audio = Vec::new();
+        stream.read_to_end(&mut audio).await.unwrap();
+        assert_eq!(audio, b"01234");
+
+        wait_for_downloads(&audio_cache).await;
+        let stats = audio_cache.get_stats();
+        assert_eq!(stats.file_count, 0);
+        assert_eq!(stats.upstream_errors, 1);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stalled_download_times_out() {
+        let dir = create_test_dir();
+        let mut audio_cache = AudioCache::new_with_dir(&dir, 1000).unwrap();
+        audio_cache.upstream_read_timeout = Duration::from_millis(100);
+        let (audio_link, chunk_sender) = start_audio_host(10);
+
+        let mut stream = stream_cached_audio(audio_cache.open(&audio_link).await.unwrap());
+        // The connection is kept open, but nothing more is sent.
+        chunk_sender.send(b"01234".to_vec()).unwrap();
+        let mut audio = Vec::new();
+        stream.read_to_end(&mut audio).await.unwrap();
+        assert_eq!(audio, b"01234");
+
+        wait_for_downloads(&audio_cache).await;
+        let stats = audio_cache.get_stats();
+        assert_eq!(stats.file_count, 0);
+        assert_eq!(stats.upstream_errors, 1);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        drop(chunk_sender);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
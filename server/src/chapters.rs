@@ -0,0 +1,136 @@
+//! Chapters of an episode, extracted from the timestamps in its description.
+//!
+//! Chapters are served in the [Podcasting 2.0 chapters format](https://github.com/Podcastindex-org/podcast-namespace/blob/main/chapters/jsonChapters.md),
+//! so that podcast apps can show them and let listeners jump between them.
+
+use crate::description::DescriptionTimestamp;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use utoipa::ToSchema;
+
+/// Version of the Podcasting 2.0 chapters format that `ChaptersDocument` follows.
+const CHAPTERS_FORMAT_VERSION: &str = "1.2.0";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Chapter {
+    /// Offset (in seconds) from the start of the episode.
+    start_time: u64,
+    /// Offset (in seconds) of the end of the chapter, which is unknown for the
+    /// last chapter if the episode has no length.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_time: Option<u64>,
+    title: String,
+}
+
+/// Turns the timestamps in an episode's description into chapters.
+///
+/// A single timestamp is more likely to be a passing reference than a table of
+/// contents, so at least two are needed. Timestamps past the end of the episode
+/// are ignored, as are repeats of an earlier timestamp.
+pub fn extract_chapters(
+    timestamps: &[DescriptionTimestamp],
+    length_in_seconds: i32,
+) -> Vec<Chapter> {
+    let length_in_seconds_or = u64::try_from(length_in_seconds)
+        .ok()
+        .filter(|length_in_seconds| *length_in_seconds > 0);
+
+    let mut timestamps: Vec<&DescriptionTimestamp> = timestamps
+        .iter()
+        .filter(|timestamp| match length_in_seconds_or {
+            Some(length_in_seconds) => timestamp.get_seconds() < length_in_seconds,
+            None => true,
+        })
+        .collect();
+    // Sorting is stable, so the first of any repeated timestamps is kept.
+    timestamps.sort_by_key(|timestamp| timestamp.get_seconds());
+    timestamps.dedup_by_key(|timestamp| timestamp.get_seconds());
+    if timestamps.len() < 2 {
+        return Vec::new();
+    }
+
+    let end_times = timestamps
+        .iter()
+        .skip(1)
+        .map(|timestamp| Some(timestamp.get_seconds()))
+        .chain(std::iter::once(length_in_seconds_or));
+    timestamps
+        .iter()
+        .zip(end_times)
+        .map(|(timestamp, end_time)| Chapter {
+            start_time: timestamp.get_seconds(),
+            end_time,
+            title: timestamp.get_label().to_string(),
+        })
+        .collect()
+}
+
+/// An episode's chapters in the Podcasting 2.0 format.
+#[derive(Serialize, ToSchema)]
+pub struct ChaptersDocument {
+    /// Version of the chapters format.
+    #[schema(value_type = String)]
+    version: &'static str,
+    chapters: Vec<Chapter>,
+}
+
+impl ChaptersDocument {
+    pub fn new(chapters: Vec<Chapter>) -> Self {
+        Self {
+            version: CHAPTERS_FORMAT_VERSION,
+            chapters,
+        }
+    }
+}
+
+// TODO - Abstract this into a procedural macro along with all other Responder impl blocks in other structs.
+impl<'r> rocket::response::Responder<'r, 'static> for ChaptersDocument {
+    fn respond_to(
+        self,
+        _request: &'r rocket::request::Request,
+    ) -> Result<rocket::response::Response<'static>, rocket::http::Status> {
+        let json_string = serde_json::json!(self).to_string();
+        rocket::Response::build()
+            .header(rocket::http::ContentType::new(
+                "application",
+                "json+chapters",
+            ))
+            .sized_body(json_string.len(), std::io::Cursor::new(json_string))
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_chapters() {
+        let timestamps = [
+            DescriptionTimestamp::new(600, "Second call".to_string()),
+            DescriptionTimestamp::new(0, "Intro".to_string()),
+            DescriptionTimestamp::new(60, "First call".to_string()),
+            DescriptionTimestamp::new(60, "Repeated".to_string()),
+            DescriptionTimestamp::new(5000, "Past the end".to_string()),
+        ];
+        let chapter = |start_time: u64, end_time: Option<u64>, title: &str| Chapter {
+            start_time,
+            end_time,
+            title: title.to_string(),
+        };
+        assert_eq!(
+            extract_chapters(&timestamps, 3600),
+            [
+                chapter(0, Some(60), "Intro"),
+                chapter(60, Some(600), "First call"),
+                chapter(600, Some(3600), "Second call")
+            ]
+        );
+        assert_eq!(
+            extract_chapters(&timestamps, 0).last(),
+            Some(&chapter(5000, None, "Past the end"))
+        );
+        assert!(extract_chapters(&timestamps[..1], 3600).is_empty());
+    }
+}
@@ -0,0 +1,192 @@
+//! Processing of podcast descriptions, which are HTML of varying quality upstream.
+//!
+//! Each description is sanitized so that it's safe to embed in a page or RSS
+//! feed, and converted to plain text for searching and for clients that don't
+//! render HTML. Links and timestamps (such as "12:34 - Topic") are extracted
+//! from it along the way.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Closing tags after which a line break is inserted in the plain text, along with `<br>`.
+const BLOCK_END_TAGS: [&str; 13] = [
+    "</p>",
+    "</div>",
+    "</li>",
+    "</tr>",
+    "</blockquote>",
+    "</pre>",
+    "</h1>",
+    "</h2>",
+    "</h3>",
+    "</h4>",
+    "</h5>",
+    "</h6>",
+    "<br>",
+];
+
+/// A point in the episode that the description refers to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DescriptionTimestamp {
+    /// Offset from the start of the episode.
+    seconds: u64,
+    /// Text following the timestamp on the same line.
+    label: String,
+}
+
+impl DescriptionTimestamp {
+    pub fn new(seconds: u64, label: String) -> Self {
+        Self { seconds, label }
+    }
+
+    pub fn get_seconds(&self) -> u64 {
+        self.seconds
+    }
+
+    pub fn get_label(&self) -> &str {
+        &self.label
+    }
+}
+
+pub struct ProcessedDescription {
+    /// Sanitized HTML.
+    pub html: String,
+    pub text: String,
+    /// Every link in the description, in order of first appearance.
+    pub links: Vec<String>,
+    pub timestamps: Vec<DescriptionTimestamp>,
+}
+
+pub fn process_description(raw_description: &str) -> ProcessedDescription {
+    let html = ammonia::clean(raw_description);
+    let text = html_to_text(&html);
+
+    let mut links: Vec<String> = Vec::new();
+    let href_links = html
+        .split("href=\"")
+        .skip(1)
+        .filter_map(|rest| rest.split('"').next())
+        .map(decode_entities);
+    let bare_links = text
+        .split_whitespace()
+        .filter(|word| word.starts_with("http://") || word.starts_with("https://"))
+        .map(|word| {
+            word.trim_end_matches(|c: char| ".,;:!?)]'\"".contains(c))
+                .to_string()
+        });
+    for link in href_links.chain(bare_links) {
+        if !links.contains(&link) {
+            links.push(link);
+        }
+    }
+
+    let timestamps = text.lines().filter_map(parse_timestamp_line).collect();
+
+    ProcessedDescription {
+        html,
+        text,
+        links,
+        timestamps,
+    }
+}
+
+// Strips all tags from sanitized HTML, keeping a line per block of text.
+fn html_to_text(html: &str) -> String {
+    let mut html = html.to_string();
+    for tag in BLOCK_END_TAGS {
+        html = html.replace(tag, &format!("{}\n", tag));
+    }
+    let text = decode_entities(&ammonia::Builder::empty().clean(&html).to_string());
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<&str>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Ammonia escapes text as it serializes it, which only produces these entities.
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&nbsp;", "\u{a0}")
+        .replace("&amp;", "&")
+}
+
+// Parses a line that starts with a timestamp, such as "(1:02:03) - Topic".
+fn parse_timestamp_line(line: &str) -> Option<DescriptionTimestamp> {
+    let line = line.trim_start_matches(|c: char| c.is_whitespace() || "([-*•".contains(c));
+    let (token, label) = line.split_once(' ').unwrap_or((line, ""));
+    let token = token.trim_end_matches(|c: char| !c.is_ascii_digit());
+
+    let parts: Vec<&str> = token.split(':').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return None;
+    }
+    let mut seconds: u64 = 0;
+    for (i, part) in parts.iter().enumerate() {
+        // Hours (or minutes, if there are no hours) may be unpadded, but the rest must be two digits.
+        let (min_length, max_length, max_value) = if i == 0 { (1, 3, 999) } else { (2, 2, 59) };
+        if part.len() < min_length
+            || part.len() > max_length
+            || !part.chars().all(|c| c.is_ascii_digit())
+        {
+            return None;
+        }
+        let value: u64 = part.parse().ok()?;
+        if value > max_value {
+            return None;
+        }
+        seconds = seconds * 60 + value;
+    }
+
+    let label = label
+        .trim_start_matches(|c: char| c.is_whitespace() || "-–—:|)]".contains(c))
+        .trim_end();
+    if label.is_empty() {
+        return None;
+    }
+    Some(DescriptionTimestamp::new(seconds, label.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_process_description() {
+        let description = process_description(
+            r#"<p>Hello &amp; <b>welcome</b><script>alert(1)</script></p><p>See <a href="https://a.com/?x=1&amp;y=2" onclick="x()">this</a> or https://b.com.</p><ul><li>00:00 - Intro</li><li>(1:02:03) Call one</li><li>at 5:30 pm</li><li>12:34</li></ul>"#,
+        );
+        assert!(!description.html.contains("script"));
+        assert!(!description.html.contains("onclick"));
+        assert!(description.html.contains("<b>welcome</b>"));
+        assert_eq!(
+            description.text,
+            "Hello & welcome\nSee this or https://b.com.\n00:00 - Intro\n(1:02:03) Call one\nat 5:30 pm\n12:34"
+        );
+        assert_eq!(
+            description.links,
+            ["https://a.com/?x=1&y=2", "https://b.com"]
+        );
+        assert_eq!(
+            description.timestamps,
+            [
+                DescriptionTimestamp {
+                    seconds: 0,
+                    label: "Intro".to_string()
+                },
+                DescriptionTimestamp {
+                    seconds: 3723,
+                    label: "Call one".to_string()
+                }
+            ]
+        );
+
+        assert_eq!(
+            process_description("Plain text\n\n  description").text,
+            "Plain text\ndescription"
+        );
+    }
+}
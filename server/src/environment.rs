@@ -2,6 +2,15 @@ pub struct EnvironmentVariables {
     server_mode: ServerMode,
     meilisearch_host: String,
     meilisearch_api_key: String,
+    saved_feeds_path: String,
+    taxonomy_path: String,
+    tag_aliases_path: String,
+    transcripts_dir: String,
+    audio_cache_dir: String,
+    audio_cache_max_bytes: u64,
+    public_base_url: String,
+    log_format: LogFormat,
+    log_level: String,
 }
 
 impl EnvironmentVariables {
@@ -22,6 +31,34 @@ impl EnvironmentVariables {
         }
     }
 
+    fn parse_audio_cache_max_bytes_or_panic(raw_audio_cache_max_bytes: String) -> u64 {
+        match raw_audio_cache_max_bytes.parse() {
+            Ok(audio_cache_max_bytes) => audio_cache_max_bytes,
+            Err(_) => {
+                panic!("AUDIO_CACHE_MAX_BYTES environment variable must be a number of bytes!")
+            }
+        }
+    }
+
+    fn parse_log_format_or_panic(raw_log_format: String) -> LogFormat {
+        if raw_log_format == RAW_PRETTY_LOG_FORMAT {
+            LogFormat::Pretty
+        } else if raw_log_format == RAW_JSON_LOG_FORMAT {
+            LogFormat::Json
+        } else {
+            panic!("LOG_FORMAT environment variable must be 'pretty' or 'json'!");
+        }
+    }
+
+    /// Default configuration, but with the given server mode regardless of `SERVER_MODE`.
+    #[cfg(test)]
+    pub fn with_server_mode(server_mode: ServerMode) -> Self {
+        Self {
+            server_mode,
+            ..Self::default()
+        }
+    }
+
     pub fn get_server_mode(&self) -> ServerMode {
         self.server_mode
     }
@@ -33,6 +70,49 @@ impl EnvironmentVariables {
     pub fn get_meilisearch_api_key(&self) -> &str {
         &self.meilisearch_api_key
     }
+
+    pub fn get_saved_feeds_path(&self) -> &str {
+        &self.saved_feeds_path
+    }
+
+    pub fn get_taxonomy_path(&self) -> &str {
+        &self.taxonomy_path
+    }
+
+    pub fn get_tag_aliases_path(&self) -> &str {
+        &self.tag_aliases_path
+    }
+
+    pub fn get_transcripts_dir(&self) -> &str {
+        &self.transcripts_dir
+    }
+
+    /// Directory that proxied audio is cached in, or `None` if the audio proxy is disabled.
+    pub fn get_audio_cache_dir(&self) -> Option<&str> {
+        if self.audio_cache_dir.is_empty() {
+            None
+        } else {
+            Some(&self.audio_cache_dir)
+        }
+    }
+
+    pub fn get_audio_cache_max_bytes(&self) -> u64 {
+        self.audio_cache_max_bytes
+    }
+
+    /// Base URL that the server is publicly reachable at, without a trailing slash.
+    pub fn get_public_base_url(&self) -> &str {
+        self.public_base_url.trim_end_matches('/')
+    }
+
+    pub fn get_log_format(&self) -> LogFormat {
+        self.log_format
+    }
+
+    /// Log filter directive, such as `info` or `fdr_show_indexer_server=debug,warn`.
+    pub fn get_log_level(&self) -> &str {
+        &self.log_level
+    }
 }
 
 impl Default for EnvironmentVariables {
@@ -47,6 +127,23 @@ impl Default for EnvironmentVariables {
                 "http://localhost:7700",
             ),
             meilisearch_api_key: Self::get_env_var_or_default("MEILISEARCH_API_KEY", ""),
+            saved_feeds_path: Self::get_env_var_or_default("SAVED_FEEDS_PATH", "saved_feeds.json"),
+            taxonomy_path: Self::get_env_var_or_default("TAXONOMY_PATH", "taxonomy.json"),
+            tag_aliases_path: Self::get_env_var_or_default("TAG_ALIASES_PATH", "tag_aliases.json"),
+            transcripts_dir: Self::get_env_var_or_default("TRANSCRIPTS_DIR", "transcripts"),
+            audio_cache_dir: Self::get_env_var_or_default("AUDIO_CACHE_DIR", ""),
+            audio_cache_max_bytes: Self::parse_audio_cache_max_bytes_or_panic(
+                Self::get_env_var_or_default("AUDIO_CACHE_MAX_BYTES", "10000000000"),
+            ),
+            public_base_url: Self::get_env_var_or_default(
+                "PUBLIC_BASE_URL",
+                "https://fdr-finder.tommyvolk.com",
+            ),
+            log_format: Self::parse_log_format_or_panic(Self::get_env_var_or_default(
+                "LOG_FORMAT",
+                RAW_PRETTY_LOG_FORMAT,
+            )),
+            log_level: Self::get_env_var_or_default("LOG_LEVEL", "info"),
         }
     }
 }
@@ -57,6 +154,25 @@ pub enum ServerMode {
     Mock, // Doesn't connect to any backend services - uses mock data and is able to run completely standalone. Good for testing and development.
 }
 
+impl ServerMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Prod => RAW_PROD_SERVER_MODE,
+            Self::Mock => RAW_MOCK_SERVER_MODE,
+        }
+    }
+}
+
 // Raw values acceptable for SERVER_MODE environment variable.
 const RAW_PROD_SERVER_MODE: &str = "prod";
 const RAW_MOCK_SERVER_MODE: &str = "mock";
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum LogFormat {
+    Pretty, // Human-readable, for local development.
+    Json,   // One JSON object per line, for log aggregation.
+}
+
+// Raw values acceptable for LOG_FORMAT environment variable.
+const RAW_PRETTY_LOG_FORMAT: &str = "pretty";
+const RAW_JSON_LOG_FORMAT: &str = "json";
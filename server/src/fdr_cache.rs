@@ -1,27 +1,106 @@
 use crate::http::get_all_podcasts;
-use crate::podcast::{Podcast, PodcastNumber};
+use crate::podcast::{Podcast, PodcastNumber, PodcastTag};
+use crate::series::{detect_series, SeriesIndex};
+use crate::tag_index::{TagIndex, TagOrder};
+use crate::tag_normalizer::TagNormalizer;
+use crate::transcripts::TranscriptStore;
 use dashmap::DashMap;
+use sha2::Digest;
 use std::error::Error;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use crate::mock::create_mock_podcast;
 
+/// Aggregate statistics about all podcasts in the cache.
+#[derive(Default)]
+pub struct CatalogueStats {
+    /// Changes whenever any podcast is added, removed or modified.
+    pub catalogue_version: String,
+    pub podcast_count: usize,
+    pub tag_count: usize,
+    pub newest_podcast_or: Option<Podcast>,
+    pub oldest_podcast_or: Option<Podcast>,
+    pub total_length_in_seconds: u64,
+}
+
+impl CatalogueStats {
+    fn new<'a>(podcasts: impl Iterator<Item = &'a Podcast>, tag_count: usize) -> Self {
+        let mut podcasts: Vec<&Podcast> = podcasts.collect();
+        // Sort so that the catalogue version doesn't depend on iteration order.
+        podcasts.sort_by(|podcast_one, podcast_two| {
+            podcast_one
+                .get_podcast_number_hash()
+                .cmp(podcast_two.get_podcast_number_hash())
+        });
+
+        let mut hasher = sha2::Sha256::new();
+        let mut total_length_in_seconds: u64 = 0;
+        for podcast in &podcasts {
+            // Podcasts serialize deterministically apart from their tag order.
+            let mut podcast_json = serde_json::json!(podcast);
+            let mut sorted_tags: Vec<&str> = podcast
+                .get_tags()
+                .iter()
+                .map(PodcastTag::to_string)
+                .collect();
+            sorted_tags.sort_unstable();
+            podcast_json["tags"] = serde_json::json!(sorted_tags);
+            hasher.update(podcast_json.to_string());
+
+            total_length_in_seconds += podcast.get_length_in_seconds().max(0) as u64;
+        }
+
+        Self {
+            catalogue_version: hex::encode(hasher.finalize())[..12].to_string(),
+            podcast_count: podcasts.len(),
+            tag_count,
+            newest_podcast_or: podcasts
+                .iter()
+                .max_by_key(|podcast| podcast.get_create_time())
+                .map(|podcast| (*podcast).clone()),
+            oldest_podcast_or: podcasts
+                .iter()
+                .min_by_key(|podcast| podcast.get_create_time())
+                .map(|podcast| (*podcast).clone()),
+            total_length_in_seconds,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct FdrCache {
     podcasts_by_num: Arc<DashMap<PodcastNumber, Podcast>>,
+    // Rebuilt whenever podcasts are ingested.
+    tag_index: Arc<RwLock<Arc<TagIndex>>>,
+    series_index: Arc<RwLock<Arc<SeriesIndex>>>,
+    catalogue_stats: Arc<RwLock<Arc<CatalogueStats>>>,
 }
 
 impl FdrCache {
-    pub async fn new_with_prod_podcasts() -> Result<Self, Box<dyn Error>> {
-        Ok(Self::new(get_all_podcasts().await?.into_iter().collect()))
+    pub async fn new_with_prod_podcasts(
+        tag_normalizer: &TagNormalizer,
+        transcript_store: &TranscriptStore,
+    ) -> Result<Self, Box<dyn Error>> {
+        Ok(Self::new(
+            get_all_podcasts(tag_normalizer, transcript_store)
+                .await?
+                .into_iter()
+                .collect(),
+        ))
     }
 
-    pub fn new_with_mock_podcasts() -> Self {
+    pub fn new_with_mock_podcasts(
+        tag_normalizer: &TagNormalizer,
+        transcript_store: &TranscriptStore,
+    ) -> Self {
         let mut podcasts: Vec<Podcast> = Vec::new();
 
         for i in 1..1000 {
             podcasts.push(create_mock_podcast(i));
         }
+        tag_normalizer.normalize_podcast_tags(&mut podcasts);
+        detect_series(&mut podcasts);
+        transcript_store.mark_transcribed_podcasts(&mut podcasts);
 
         Self::new(podcasts)
     }
@@ -29,6 +108,9 @@ impl FdrCache {
     fn new(podcasts: Vec<Podcast>) -> Self {
         let mut cache = Self {
             podcasts_by_num: Arc::from(DashMap::new()),
+            tag_index: Arc::from(RwLock::new(Arc::from(TagIndex::default()))),
+            series_index: Arc::from(RwLock::new(Arc::from(SeriesIndex::default()))),
+            catalogue_stats: Arc::from(RwLock::new(Arc::from(CatalogueStats::default()))),
         };
         cache.ingest_podcasts(podcasts.into_iter());
         cache
@@ -39,6 +121,12 @@ impl FdrCache {
             self.podcasts_by_num
                 .insert(podcast.get_podcast_number().clone(), podcast);
         }
+        let tag_index = TagIndex::new(self.iter());
+        let catalogue_stats = CatalogueStats::new(self.iter(), tag_index.get_tag_count());
+        *self.tag_index.write().unwrap() = Arc::from(tag_index);
+        *self.catalogue_stats.write().unwrap() = Arc::from(catalogue_stats);
+        let series_index = SeriesIndex::new(self.iter());
+        *self.series_index.write().unwrap() = Arc::from(series_index);
     }
 
     /// Iterator over all Podcasts in the cache.
@@ -48,6 +136,10 @@ impl FdrCache {
         self.podcasts_by_num.iter().map(|entry| entry.value())
     }
 
+    pub fn get_podcast_count(&self) -> usize {
+        self.podcasts_by_num.len()
+    }
+
     /// Get a immutable reference to a Podcast in the cache.
     ///
     /// **Locking behaviour:** May deadlock if called when holding a mutable reference into the cache.
@@ -57,4 +149,74 @@ impl FdrCache {
             None => None,
         }
     }
+
+    /// Clones of all podcasts with the given tag, newest first.
+    ///
+    /// **Locking behaviour:** May deadlock if called when holding any sort of reference into the cache.
+    pub fn get_podcasts_with_tag(&self, tag: &PodcastTag) -> Vec<Podcast> {
+        let mut podcasts: Vec<Podcast> = self
+            .iter()
+            .filter(|podcast| podcast.get_tags().contains(tag))
+            .cloned()
+            .collect();
+        podcasts.sort_by_key(|podcast| std::cmp::Reverse(podcast.get_create_time()));
+        podcasts
+    }
+
+    /// Summary of every tag in the cache, as of the last time podcasts were ingested.
+    pub fn get_tag_index(&self) -> Arc<TagIndex> {
+        self.tag_index.read().unwrap().clone()
+    }
+
+    /// Every series in the cache, as of the last time podcasts were ingested.
+    pub fn get_series_index(&self) -> Arc<SeriesIndex> {
+        self.series_index.read().unwrap().clone()
+    }
+
+    /// Number of podcasts that each tag appears in, sorted by descending count and then by tag name.
+    pub fn get_tag_counts(&self) -> Vec<(PodcastTag, usize)> {
+        self.get_tag_index()
+            .list(TagOrder::Popularity, "")
+            .into_iter()
+            .map(|summary| (summary.get_tag().clone(), summary.get_count()))
+            .collect()
+    }
+
+    /// Statistics about the cache, as of the last time podcasts were ingested.
+    pub fn get_catalogue_stats(&self) -> Arc<CatalogueStats> {
+        self.catalogue_stats.read().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_catalogue_stats() {
+        let mut fdr_cache = FdrCache::new((1..=3).map(create_mock_podcast).collect());
+        let catalogue_stats = fdr_cache.get_catalogue_stats();
+        assert_eq!(catalogue_stats.podcast_count, 3);
+        assert_eq!(catalogue_stats.tag_count, 3);
+        assert_eq!(catalogue_stats.total_length_in_seconds, 6);
+        assert_eq!(
+            catalogue_stats.newest_podcast_or,
+            Some(create_mock_podcast(3))
+        );
+        assert_eq!(
+            catalogue_stats.oldest_podcast_or,
+            Some(create_mock_podcast(1))
+        );
+
+        // The version only changes when the catalogue does.
+        assert_eq!(
+            fdr_cache.get_catalogue_stats().catalogue_version,
+            catalogue_stats.catalogue_version
+        );
+        fdr_cache.ingest_podcasts(vec![create_mock_podcast(4)].into_iter());
+        assert_ne!(
+            fdr_cache.get_catalogue_stats().catalogue_version,
+            catalogue_stats.catalogue_version
+        );
+    }
 }
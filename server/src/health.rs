@@ -0,0 +1,235 @@
+//! Liveness and readiness checks, used by Kubernetes to decide whether to
+//! restart the server and whether to route traffic to it.
+
+use crate::environment::{EnvironmentVariables, ServerMode};
+use crate::fdr_cache::FdrCache;
+use crate::search::SearchBackend;
+use crate::sync::{SyncStatus, SYNC_INTERVAL};
+use chrono::{DateTime, Utc};
+use rocket::http::Status;
+use rocket::response::{content, status};
+use rocket::{Route, State};
+use serde::Serialize;
+use std::time::Duration;
+
+// Readiness fails if this many syncs in a row are missed or fail.
+const MAX_MISSED_SYNCS: u32 = 3;
+// How long to wait for Meilisearch before considering it unreachable.
+const SEARCH_INDEX_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Serialize)]
+struct HealthCheck {
+    name: &'static str,
+    healthy: bool,
+    detail: String,
+}
+
+impl HealthCheck {
+    fn new(name: &'static str, result: Result<String, String>) -> Self {
+        match result {
+            Ok(detail) => Self {
+                name,
+                healthy: true,
+                detail,
+            },
+            Err(detail) => Self {
+                name,
+                healthy: false,
+                detail,
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct HealthReport {
+    status: &'static str,
+    checks: Vec<HealthCheck>,
+}
+
+impl HealthReport {
+    fn new(checks: Vec<HealthCheck>) -> Self {
+        let status = if checks.iter().all(|check| check.healthy) {
+            "ok"
+        } else {
+            "unavailable"
+        };
+        Self { status, checks }
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.status == "ok"
+    }
+}
+
+fn check_podcast_cache(podcast_count: usize) -> Result<String, String> {
+    if podcast_count > 0 {
+        Ok(format!("{} podcasts cached.", podcast_count))
+    } else {
+        Err("No podcasts are cached.".to_string())
+    }
+}
+
+async fn check_search_index(search_backend: &SearchBackend) -> Result<String, String> {
+    match tokio::time::timeout(
+        SEARCH_INDEX_TIMEOUT,
+        search_backend.get_indexed_podcast_count(),
+    )
+    .await
+    {
+        Ok(Ok(0)) => Err("Search index is empty.".to_string()),
+        Ok(Ok(podcast_count)) => Ok(format!("{} podcasts indexed.", podcast_count)),
+        Ok(Err(err)) => Err(format!("Search backend is unreachable: {}", err)),
+        Err(_) => Err("Search backend timed out.".to_string()),
+    }
+}
+
+fn check_sync(
+    last_successful_sync_time_or: Option<DateTime<Utc>>,
+    now: DateTime<Utc>,
+) -> Result<String, String> {
+    let last_successful_sync_time = match last_successful_sync_time_or {
+        Some(last_successful_sync_time) => last_successful_sync_time,
+        None => return Err("Podcasts have never been synced.".to_string()),
+    };
+    let max_sync_age = chrono::Duration::from_std(SYNC_INTERVAL * MAX_MISSED_SYNCS).unwrap();
+    if now - last_successful_sync_time > max_sync_age {
+        Err(format!(
+            "Last successful sync was at {}, which is too long ago.",
+            last_successful_sync_time.to_rfc3339()
+        ))
+    } else {
+        Ok(format!(
+            "Last successful sync was at {}.",
+            last_successful_sync_time.to_rfc3339()
+        ))
+    }
+}
+
+/// Always succeeds while the server is able to handle requests.
+#[get("/livez")]
+pub fn livez_handler() -> content::Json<String> {
+    content::Json(serde_json::json!({ "status": "ok" }).to_string())
+}
+
+/// Succeeds if the server has podcasts to serve and is able to search them.
+#[get("/readyz")]
+pub async fn readyz_handler(
+    fdr_cache: &State<FdrCache>,
+    search_backend: &State<SearchBackend>,
+    sync_status: &State<SyncStatus>,
+    env_vars: &State<EnvironmentVariables>,
+) -> status::Custom<content::Json<String>> {
+    let mut checks = vec![
+        HealthCheck::new(
+            "podcastCache",
+            check_podcast_cache(fdr_cache.get_podcast_count()),
+        ),
+        HealthCheck::new("searchIndex", check_search_index(search_backend).await),
+    ];
+    // Podcasts are only synced periodically in prod mode.
+    if env_vars.get_server_mode() == ServerMode::Prod {
+        checks.push(HealthCheck::new(
+            "sync",
+            check_sync(sync_status.get_last_successful_sync_time(), Utc::now()),
+        ));
+    }
+
+    let report = HealthReport::new(checks);
+    let status = if report.is_healthy() {
+        Status::Ok
+    } else {
+        Status::ServiceUnavailable
+    };
+    status::Custom(status, content::Json(serde_json::json!(report).to_string()))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![livez_handler, readyz_handler]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::Metrics;
+    use crate::tag_normalizer::TagNormalizer;
+    use crate::transcripts::TranscriptStore;
+    use rocket::local::asynchronous::Client;
+
+    async fn get_readyz_response(
+        search_backend: SearchBackend,
+        sync_status: SyncStatus,
+    ) -> (Status, serde_json::Value) {
+        let fdr_cache = FdrCache::new_with_mock_podcasts(
+            &TagNormalizer::default(),
+            &TranscriptStore::new_mock(),
+        );
+        let rocket = rocket::build()
+            .manage(fdr_cache)
+            .manage(search_backend)
+            .manage(sync_status)
+            .manage(EnvironmentVariables::with_server_mode(ServerMode::Prod))
+            .mount("/", routes());
+        let client = Client::tracked(rocket).await.unwrap();
+        let response = client.get("/readyz").dispatch().await;
+        let status = response.status();
+        (
+            status,
+            serde_json::from_str(&response.into_string().await.unwrap()).unwrap(),
+        )
+    }
+
+    fn get_unhealthy_checks(report: &serde_json::Value) -> Vec<&str> {
+        report["checks"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|check| !check["healthy"].as_bool().unwrap())
+            .map(|check| check["name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_readyz_handler() {
+        let mock_search_backend = SearchBackend::new_mock(
+            Metrics::new(),
+            TagNormalizer::default(),
+            TranscriptStore::new_mock(),
+        );
+        let fresh_sync_status = SyncStatus::default();
+        fresh_sync_status.record_success();
+        let (status, report) =
+            get_readyz_response(mock_search_backend.clone(), fresh_sync_status.clone()).await;
+        assert_eq!(status, Status::Ok);
+        assert_eq!(report["status"], "ok");
+
+        // Nothing listens on port 1, so connections are refused straight away.
+        let unreachable_search_backend = SearchBackend::new_prod_without_reset(
+            "http://127.0.0.1:1".to_string(),
+            String::new(),
+            Metrics::new(),
+            TagNormalizer::default(),
+            TranscriptStore::new_mock(),
+        );
+        let (status, report) =
+            get_readyz_response(unreachable_search_backend, fresh_sync_status).await;
+        assert_eq!(status, Status::ServiceUnavailable);
+        assert_eq!(report["status"], "unavailable");
+        assert_eq!(get_unhealthy_checks(&report), vec!["searchIndex"]);
+
+        let stale_sync_status = SyncStatus::default();
+        stale_sync_status.record_success_at(Utc::now() - chrono::Duration::hours(4));
+        let (status, report) = get_readyz_response(mock_search_backend, stale_sync_status).await;
+        assert_eq!(status, Status::ServiceUnavailable);
+        assert_eq!(get_unhealthy_checks(&report), vec!["sync"]);
+    }
+
+    #[test]
+    fn test_check_sync() {
+        let now = Utc::now();
+        assert!(check_sync(None, now).is_err());
+        assert!(check_sync(Some(now), now).is_ok());
+        assert!(check_sync(Some(now - chrono::Duration::hours(2)), now).is_ok());
+        assert!(check_sync(Some(now - chrono::Duration::hours(4)), now).is_err());
+    }
+}
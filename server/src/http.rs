@@ -1,4 +1,7 @@
-use crate::podcast::{Podcast, PodcastNumber, PodcastTag};
+use crate::podcast::{MediaLink, Podcast, PodcastNumber, PodcastTag};
+use crate::series::detect_series;
+use crate::tag_normalizer::TagNormalizer;
+use crate::transcripts::TranscriptStore;
 use serde::Deserialize;
 use std::{collections::HashMap, error::Error};
 
@@ -24,17 +27,22 @@ struct JsonTag {
     tag_name: String,
 }
 
-fn json_podcast_to_podcast(mut json_podcast: JsonPodcast) -> Podcast {
+fn json_podcast_to_podcast(json_podcast: JsonPodcast) -> Podcast {
+    let audio_link = json_podcast.urls["audio"].clone();
+    // Episodes without a usable number are specials, which are identified by their audio link instead.
+    let podcast_number = match &json_podcast.num {
+        Some(num) => num.to_string().parse().unwrap_or_else(|_| {
+            tracing::warn!(num = %num, title = %json_podcast.title, "Podcast has an invalid number");
+            PodcastNumber::new_special(&audio_link)
+        }),
+        None => PodcastNumber::new_special(&audio_link),
+    };
     Podcast::new(
         json_podcast.title,
         json_podcast.description,
-        json_podcast.urls.remove("audio").unwrap(),
+        audio_link,
         json_podcast.length,
-        PodcastNumber::new(
-            json_podcast
-                .num
-                .unwrap_or_else(|| serde_json::Number::from(0)),
-        ),
+        podcast_number,
         chrono::DateTime::parse_from_rfc3339(&json_podcast.date)
             .unwrap()
             .timestamp(),
@@ -44,6 +52,14 @@ fn json_podcast_to_podcast(mut json_podcast: JsonPodcast) -> Podcast {
             .map(|tag| PodcastTag::new(tag.tag_name))
             .collect(),
     )
+    .with_media_links(
+        json_podcast
+            .urls
+            .into_iter()
+            .filter(|(_, url)| !url.is_empty())
+            .map(|(name, url)| MediaLink::new(name, url))
+            .collect(),
+    )
 }
 
 async fn get_podcasts_page(page_number: i32) -> Result<Vec<Podcast>, Box<dyn Error>> {
@@ -70,7 +86,10 @@ async fn get_podcasts_page(page_number: i32) -> Result<Vec<Podcast>, Box<dyn Err
         .collect())
 }
 
-pub async fn get_all_podcasts() -> Result<Vec<Podcast>, Box<dyn Error>> {
+pub async fn get_all_podcasts(
+    tag_normalizer: &TagNormalizer,
+    transcript_store: &TranscriptStore,
+) -> Result<Vec<Podcast>, Box<dyn Error>> {
     let mut current_page_number = 0;
     let mut results: Vec<Podcast> = Vec::new();
     loop {
@@ -81,5 +100,9 @@ pub async fn get_all_podcasts() -> Result<Vec<Podcast>, Box<dyn Error>> {
         results.append(&mut page_results);
         current_page_number += 1;
     }
+    // Upstream tags are free text, so the same tag may be written in several ways.
+    tag_normalizer.normalize_podcast_tags(&mut results);
+    detect_series(&mut results);
+    transcript_store.mark_transcribed_podcasts(&mut results);
     Ok(results)
 }
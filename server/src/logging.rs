@@ -0,0 +1,133 @@
+//! Structured logging and per-request tracing.
+//!
+//! Every request is assigned an ID, which is returned in the `X-Request-Id`
+//! response header and attached to the spans of handlers that call out to the
+//! search backend, so that a slow request can be matched to the Meilisearch
+//! calls that it made.
+
+use crate::environment::{EnvironmentVariables, LogFormat};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::http::Header;
+use rocket::request::{FromRequest, Outcome};
+use rocket::{Data, Request, Response};
+use std::time::Instant;
+use tracing_subscriber::EnvFilter;
+
+const REQUEST_ID_HEADER: &str = "X-Request-Id";
+// Longest client-supplied request ID that will be reused rather than replaced.
+const MAX_REQUEST_ID_LENGTH: usize = 64;
+
+/// Sets up the global logger. Must be called once, before anything is logged.
+pub fn init_logging(env_vars: &EnvironmentVariables) {
+    let env_filter = match EnvFilter::try_new(env_vars.get_log_level()) {
+        Ok(env_filter) => env_filter,
+        Err(error) => panic!(
+            "LOG_LEVEL environment variable is not a valid log filter! Raw error: {}",
+            error
+        ),
+    };
+    // Rocket's own logs are forwarded to this logger, which does its own
+    // formatting, so Rocket shouldn't add terminal colors to them.
+    yansi::Paint::disable();
+    let subscriber = tracing_subscriber::fmt().with_env_filter(env_filter);
+    match env_vars.get_log_format() {
+        LogFormat::Pretty => subscriber.init(),
+        LogFormat::Json => subscriber.json().with_current_span(true).init(),
+    }
+}
+
+/// ID of the current request. Reuses the ID that the client sent in the
+/// `X-Request-Id` header if it's reasonable, and otherwise generates one.
+#[derive(Clone, Debug)]
+pub struct RequestId(String);
+
+impl RequestId {
+    fn from_request(request: &Request) -> Self {
+        let header_or = request.headers().get_one(REQUEST_ID_HEADER);
+        match header_or.filter(|header| Self::is_valid(header)) {
+            Some(header) => Self(header.to_string()),
+            None => Self(uuid::Uuid::new_v4().to_string()),
+        }
+    }
+
+    fn is_valid(request_id: &str) -> bool {
+        !request_id.is_empty()
+            && request_id.len() <= MAX_REQUEST_ID_LENGTH
+            && request_id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    }
+}
+
+impl std::fmt::Display for RequestId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for RequestId {
+    type Error = std::convert::Infallible;
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, Self::Error> {
+        Outcome::Success(
+            request
+                .local_cache(|| RequestId::from_request(request))
+                .clone(),
+        )
+    }
+}
+
+/// Fairing that assigns each request an ID and logs every response.
+pub struct RequestTracingFairing;
+
+// Start time of a request, stored in the request's local cache.
+struct RequestStartTime(Option<Instant>);
+
+#[rocket::async_trait]
+impl Fairing for RequestTracingFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request Tracing",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _data: &mut Data<'_>) {
+        request.local_cache(|| RequestStartTime(Some(Instant::now())));
+        request.local_cache(|| RequestId::from_request(request));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let request_id = request.local_cache(|| RequestId::from_request(request));
+        response.set_header(Header::new(REQUEST_ID_HEADER, request_id.to_string()));
+
+        let duration_ms = request
+            .local_cache(|| RequestStartTime(None))
+            .0
+            .map(|start_time| start_time.elapsed().as_millis() as u64);
+        tracing::info!(
+            request_id = %request_id,
+            method = %request.method(),
+            path = %request.uri().path(),
+            status = response.status().code,
+            duration_ms,
+            "Handled request"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_id_is_valid() {
+        assert!(RequestId::is_valid("abc-123_XYZ"));
+        assert!(RequestId::is_valid(&uuid::Uuid::new_v4().to_string()));
+        assert!(!RequestId::is_valid(""));
+        assert!(!RequestId::is_valid("has space"));
+        assert!(!RequestId::is_valid("new\nline"));
+        assert!(!RequestId::is_valid(&"a".repeat(MAX_REQUEST_ID_LENGTH + 1)));
+    }
+}
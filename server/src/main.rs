@@ -1,43 +1,54 @@
 #[macro_use]
 extern crate rocket;
 
+mod analytics;
+mod audio_cache;
+mod chapters;
+mod description;
+// Public so that the URI macros generated for each route aren't flagged as unused imports.
+pub mod api;
 mod environment;
 mod fdr_cache;
+pub mod health;
 mod http;
+mod logging;
+mod metrics;
 mod mock;
+mod opml;
 mod podcast;
+mod saved_feeds;
 mod search;
-
-use crate::http::get_all_podcasts;
-use crate::podcast::{generate_rss_feed, Podcast, PodcastNumber, PodcastTag, RssFeed};
+mod series;
+mod sync;
+mod tag_index;
+mod tag_normalizer;
+mod taxonomy;
+mod transcripts;
+
+use api::Deprecated;
+use audio_cache::AudioCache;
 use environment::{EnvironmentVariables, ServerMode};
 use fdr_cache::FdrCache;
+use logging::RequestTracingFairing;
+use metrics::{Metrics, MetricsFairing};
+use rocket::http::ContentType;
 use rocket::response::{content, status};
 use rocket::{Request, State};
+use saved_feeds::SavedFeedStore;
 use search::SearchBackend;
-use search::SearchResult;
-use serde_json::{json, Map, Value};
-use std::collections::HashMap;
+use sync::SyncStatus;
+use tag_normalizer::TagNormalizer;
+use taxonomy::Taxonomy;
+use transcripts::TranscriptStore;
 
 const FAVICON_BYTES: &[u8] = include_bytes!("../../client/out/favicon.ico");
 const HTML_BYTES: &[u8] = include_bytes!("../../client/out/index.html");
 const JS_BUNDLE_BYTES: &[u8] = include_bytes!("../../client/out/bundle.js");
 
-fn parse_tag_query_string(tags: Option<String>) -> Vec<PodcastTag> {
-    match tags {
-        Some(tags) => tags
-            .split(',')
-            .map(|tag| PodcastTag::new(tag.trim().to_string()))
-            .collect(),
-        None => Vec::new(),
-    }
-}
-
 enum NotFoundResponse {
     Html(status::Custom<content::Html<&'static [u8]>>),
     JavaScript(status::Custom<content::JavaScript<&'static [u8]>>),
     Favicon(Box<status::Custom<content::Custom<&'static [u8]>>>),
-    NotFound(status::NotFound<String>),
 }
 
 impl<'r> rocket::response::Responder<'r, 'static> for NotFoundResponse {
@@ -49,7 +60,6 @@ impl<'r> rocket::response::Responder<'r, 'static> for NotFoundResponse {
             NotFoundResponse::Html(html) => html.respond_to(request),
             NotFoundResponse::JavaScript(javascript) => javascript.respond_to(request),
             NotFoundResponse::Favicon(favicon) => favicon.respond_to(request),
-            NotFoundResponse::NotFound(not_found) => not_found.respond_to(request),
         }
     }
 }
@@ -61,19 +71,8 @@ fn not_found_handler(req: &Request) -> NotFoundResponse {
         None => "".to_string(),
     };
 
-    if req
-        .uri()
-        .path()
-        .split('/')
-        .find(|chunk| !chunk.is_empty())
-        .unwrap_or_else(|| "".into())
-        == "api"
-    {
-        NotFoundResponse::NotFound(status::NotFound(format!(
-            "404 - API path '{}' does not exist!",
-            req.uri().path()
-        )))
-    } else if last_chunk == "bundle.js" {
+    // Unknown API paths are handled by the catchers in `api::error`.
+    if last_chunk == "bundle.js" {
         NotFoundResponse::JavaScript(status::Custom(
             rocket::http::Status::Ok,
             content::JavaScript(JS_BUNDLE_BYTES),
@@ -91,225 +90,116 @@ fn not_found_handler(req: &Request) -> NotFoundResponse {
     }
 }
 
+/// Always succeeds, so it says nothing about whether the server can serve traffic. Use `/readyz` instead.
 #[get("/healthz")]
-async fn healthz_handler() -> content::Html<String> {
-    content::Html("<html><body><h1>200 OK</h1>Service ready.</body></html>".to_string())
-}
-
-// TODO - Guard this handler with an API key or something
-#[get("/reset")]
-async fn reset_handler(search_backend: &State<SearchBackend>) {
-    search_backend.reset().await
-}
-
-#[get("/podcasts/<podcast_num>")]
-fn get_podcast_handler(
-    podcast_num: String,
-    fdr_cache: &State<FdrCache>,
-) -> Result<Podcast, status::NotFound<String>> {
-    let podcast_or = match podcast_num.parse::<serde_json::Number>() {
-        Ok(num) => fdr_cache.get_podcast(&PodcastNumber::new(num)),
-        Err(_) => None,
-    };
-
-    match podcast_or {
-        Some(podcast) => Ok(podcast.clone()),
-        None => Err(status::NotFound("Podcast does not exist".to_string())),
-    }
-}
-
-// TODO - Rename min_length_seconds param to minLengthSeconds and max_length_seconds param to maxLengthSeconds
-// and update Typescript API file to match.
-#[get("/search/podcasts?<query>&<limit>&<offset>&<tags>&<min_length_seconds>&<max_length_seconds>")]
-async fn search_podcasts_handler<'a>(
-    query: Option<String>,
-    limit: Option<usize>,
-    offset: Option<usize>,
-    tags: Option<String>,
-    min_length_seconds: Option<usize>,
-    max_length_seconds: Option<usize>,
-    search_backend: &State<SearchBackend>,
-) -> SearchResult {
-    search_backend
-        .search(
-            &query,
-            &parse_tag_query_string(tags),
-            limit,
-            offset.unwrap_or(0),
-            min_length_seconds,
-            max_length_seconds,
-        )
-        .await
-}
-
-// TODO - Rename min_length_seconds param to minLengthSeconds and max_length_seconds param to maxLengthSeconds
-// and update Typescript API file to match.
-#[get("/search/podcasts/rss?<query>&<tags>&<min_length_seconds>&<max_length_seconds>")]
-async fn search_podcasts_as_rss_feed_handler<'a>(
-    query: Option<String>,
-    tags: Option<String>,
-    min_length_seconds: Option<usize>,
-    max_length_seconds: Option<usize>,
-    search_backend: &State<SearchBackend>,
-) -> RssFeed {
-    let search_result = search_backend
-        .search(
-            &query,
-            &parse_tag_query_string(tags),
-            None,
-            0,
-            min_length_seconds,
-            max_length_seconds,
-        )
-        .await;
-
-    // TODO - Fix RSS feed naming now that we support tag filtering.
-    generate_rss_feed(
-        search_result.get_hits(),
-        &format!(
-            "Freedomain Custom Feed: {}",
-            query.clone().unwrap_or_default()
-        ),
-        &format!(
-            "A generated feed containing all Freedomain podcasts about: {}",
-            query.unwrap_or_default()
-        ),
+async fn healthz_handler() -> Deprecated<content::Html<String>> {
+    Deprecated::new(
+        content::Html("<html><body><h1>200 OK</h1>Service ready.</body></html>".to_string()),
+        "/readyz",
     )
 }
 
-// TODO - Find a way to reduce the number of arguments so we can remove this.
-#[allow(clippy::too_many_arguments)]
-// TODO - Rename min_length_seconds param to minLengthSeconds and max_length_seconds param to maxLengthSeconds
-// and update Typescript API file to match.
-#[get("/filteredTagsWithCounts?<query>&<limit>&<offset>&<tags>&<filter>&<min_length_seconds>&<max_length_seconds>")]
-async fn get_filtered_tags_with_counts_handler<'a>(
-    query: Option<String>,
-    limit: Option<usize>,
-    offset: Option<usize>,
-    tags: Option<String>,
-    filter: Option<String>,
-    min_length_seconds: Option<usize>,
-    max_length_seconds: Option<usize>,
-    search_backend: &State<SearchBackend>,
-) -> content::Json<String> {
-    let parsed_tags = parse_tag_query_string(tags);
-
-    let podcasts: Vec<Podcast> = search_backend
-        .search(
-            &query,
-            &parsed_tags,
-            None,
-            0,
-            min_length_seconds,
-            max_length_seconds,
-        )
-        .await
-        .take_hits()
-        .into_iter()
-        .collect();
-
-    let mut counts_by_tag = HashMap::new();
-    for podcast in &podcasts {
-        for tag in podcast.get_tags() {
-            match counts_by_tag.get_mut(tag) {
-                Some(count) => {
-                    *count += 1;
-                }
-                None => {
-                    counts_by_tag.insert(tag.clone(), 1);
-                }
-            };
-        }
-    }
-
-    // Delete tags that have already been selected.
-    for tag in &parsed_tags {
-        counts_by_tag.remove(tag);
-    }
-
-    let mut counts_list: Vec<(PodcastTag, usize)> = counts_by_tag.into_iter().collect();
-    if let Some(filter) = filter {
-        counts_list.retain(|(tag, _)| {
-            tag.to_string()
-                .to_lowercase()
-                .contains(&filter.to_lowercase())
-        });
-    }
-    counts_list.sort_by(|(tag_one, count_one), (tag_two, count_two)| {
-        let count_ordering = count_two.cmp(count_one);
-        if count_ordering == std::cmp::Ordering::Equal {
-            return tag_one
-                .to_string()
-                .to_lowercase()
-                .cmp(&tag_two.to_string().to_lowercase());
-        } else {
-            count_ordering
-        }
-    });
-    let pretrimmed_tag_count = counts_list.len();
-    if let Some(mut offset) = offset {
-        // Over-draining causes a panic.
-        // Here we're making sure that we
-        // don't drain more items than
-        // exist in the array.
-        if offset > counts_list.len() {
-            offset = counts_list.len();
-        }
-        counts_list.drain(0..offset);
-    }
-    if let Some(limit) = limit {
-        counts_list.truncate(limit);
-    }
-    let trimmed_tag_count = counts_list.len();
-
-    let json_tag_array: Value = counts_list
-        .into_iter()
-        .map(|(tag, count)| {
-            let mut obj = Map::new();
-            obj.insert("tag".to_string(), Value::String(tag.clone_to_string()));
-            obj.insert("count".to_string(), json!(count));
-            Value::Object(obj)
-        })
-        .collect();
-
-    let mut obj = Map::new();
-    obj.insert("tags".to_string(), json_tag_array);
-    obj.insert(
-        "remainingTagCount".to_string(),
-        json!(pretrimmed_tag_count - trimmed_tag_count),
-    );
-    let json_obj = Value::Object(obj);
-
-    content::Json(json_obj.to_string())
+#[get("/metrics")]
+fn metrics_handler(metrics: &State<Metrics>) -> content::Custom<String> {
+    content::Custom(
+        ContentType::with_params("text", "plain", ("version", "0.0.4")),
+        metrics.encode(),
+    )
 }
 
 #[rocket::launch]
 async fn rocket() -> _ {
     let env_vars = EnvironmentVariables::default();
+    logging::init_logging(&env_vars);
 
     let server_mode = env_vars.get_server_mode();
 
     match server_mode {
         ServerMode::Prod => {
-            println!("Running in production mode.");
+            tracing::info!("Running in production mode.");
         }
         ServerMode::Mock => {
-            println!("Running in mock mode.");
+            tracing::info!("Running in mock mode.");
         }
     }
 
+    let metrics = Metrics::new();
+
+    let tag_normalizer = match server_mode {
+        ServerMode::Prod => {
+            match TagNormalizer::new_with_file(std::path::Path::new(
+                env_vars.get_tag_aliases_path(),
+            )) {
+                Ok(tag_normalizer) => tag_normalizer,
+                Err(error) => panic!(
+                    "Encountered error loading tag aliases from '{}'. Raw error: {}",
+                    env_vars.get_tag_aliases_path(),
+                    error
+                ),
+            }
+        }
+        ServerMode::Mock => TagNormalizer::default(),
+    };
+    tracing::info!(
+        alias_count = tag_normalizer.get_alias_count(),
+        "Loaded tag aliases"
+    );
+
+    let transcript_store = match server_mode {
+        ServerMode::Prod => {
+            match TranscriptStore::new_with_dir(std::path::Path::new(
+                env_vars.get_transcripts_dir(),
+            )) {
+                Ok(transcript_store) => transcript_store,
+                Err(error) => panic!(
+                    "Encountered error loading transcripts from '{}'. Raw error: {}",
+                    env_vars.get_transcripts_dir(),
+                    error
+                ),
+            }
+        }
+        ServerMode::Mock => TranscriptStore::new_mock(),
+    };
+    tracing::info!(
+        transcript_count = transcript_store.get_transcript_count(),
+        "Loaded transcripts"
+    );
+
+    let audio_cache_or = match env_vars.get_audio_cache_dir() {
+        Some(audio_cache_dir) => {
+            match AudioCache::new_with_dir(
+                std::path::Path::new(audio_cache_dir),
+                env_vars.get_audio_cache_max_bytes(),
+            ) {
+                Ok(audio_cache) => {
+                    let stats = audio_cache.get_stats();
+                    tracing::info!(stats = ?stats, "Loaded audio cache");
+                    Some(audio_cache)
+                }
+                Err(error) => panic!(
+                    "Encountered error loading audio cache from '{}'. Raw error: {}",
+                    audio_cache_dir, error
+                ),
+            }
+        }
+        None => {
+            tracing::info!("Audio proxy is disabled.");
+            None
+        }
+    };
+
+    let startup_time = std::time::Instant::now();
+
     let fdr_cache = match server_mode {
         ServerMode::Prod => {
-            println!("Fetching podcasts and building cache...");
-            let fdr_cache = FdrCache::new_with_prod_podcasts().await.unwrap();
-            println!("Done.");
-            fdr_cache
+            tracing::info!("Fetching podcasts and building cache...");
+            FdrCache::new_with_prod_podcasts(&tag_normalizer, &transcript_store)
+                .await
+                .unwrap()
         }
         ServerMode::Mock => {
-            println!("Generating mock podcasts...");
-            let fdr_cache = FdrCache::new_with_mock_podcasts();
-            println!("Done.");
-            fdr_cache
+            tracing::info!("Generating mock podcasts...");
+            FdrCache::new_with_mock_podcasts(&tag_normalizer, &transcript_store)
         }
     };
 
@@ -318,56 +208,102 @@ async fn rocket() -> _ {
             let search_backend = match SearchBackend::new_prod(
                 env_vars.get_meilisearch_host().to_string(),
                 env_vars.get_meilisearch_api_key().to_string(),
+                metrics.clone(),
+                tag_normalizer.clone(),
+                transcript_store.clone(),
             )
             .await {
                 Ok(search_backend) => search_backend,
                 Err(error) => panic!("Encountered error connecting to Meilisearch. This likely means that either something is wrong with your environment variables, or your Meilisearch server is not responding. Raw error: {}", error)
             };
 
-            println!("Ingesting search index...");
-            search_backend
-                .ingest_podcasts_or_panic(fdr_cache.iter())
-                .await;
-            println!("Done.");
+            tracing::info!("Ingesting search index...");
+            if let Err(error) = search_backend.ingest_podcasts(fdr_cache.iter()).await {
+                panic!(
+                    "Encountered error ingesting podcasts into Meilisearch. Raw error: {}",
+                    error
+                );
+            }
             search_backend
         }
-        ServerMode::Mock => SearchBackend::new_mock(),
+        ServerMode::Mock => SearchBackend::new_mock(
+            metrics.clone(),
+            tag_normalizer.clone(),
+            transcript_store.clone(),
+        ),
     };
 
-    let fdr_cache_clone = fdr_cache.clone();
-    let search_backend_clone = search_backend.clone();
+    metrics.record_successful_sync(startup_time.elapsed(), fdr_cache.get_podcast_count());
+    let sync_status = SyncStatus::default();
+    sync_status.record_success();
+    tracing::info!(
+        podcast_count = fdr_cache.get_podcast_count(),
+        "Loaded podcasts"
+    );
 
-    if server_mode == ServerMode::Prod {
-        // This task is responsible for periodically loading new podcasts.
-        tokio::spawn(async move {
-            let mut fdr_cache = fdr_cache_clone;
-            let search_backend = search_backend_clone;
-            let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
-            loop {
-                interval.tick().await;
-                let all_podcasts = get_all_podcasts().await.unwrap();
-                search_backend
-                    .ingest_podcasts_or_panic(all_podcasts.iter())
-                    .await;
-                fdr_cache.ingest_podcasts(all_podcasts.into_iter());
+    let saved_feed_store = match server_mode {
+        ServerMode::Prod => {
+            match SavedFeedStore::new_with_file(std::path::Path::new(
+                env_vars.get_saved_feeds_path(),
+            )) {
+                Ok(saved_feed_store) => saved_feed_store,
+                Err(error) => panic!(
+                    "Encountered error loading saved feeds from '{}'. Raw error: {}",
+                    env_vars.get_saved_feeds_path(),
+                    error
+                ),
             }
-        });
+        }
+        ServerMode::Mock => SavedFeedStore::new_in_memory(),
+    };
+
+    let taxonomy = match server_mode {
+        ServerMode::Prod => {
+            match Taxonomy::new_with_file(
+                std::path::Path::new(env_vars.get_taxonomy_path()),
+                &tag_normalizer,
+            ) {
+                Ok(taxonomy) => taxonomy,
+                Err(error) => panic!(
+                    "Encountered error loading taxonomy from '{}'. Raw error: {}",
+                    env_vars.get_taxonomy_path(),
+                    error
+                ),
+            }
+        }
+        ServerMode::Mock => Taxonomy::new_mock(&tag_normalizer),
+    };
+
+    if server_mode == ServerMode::Prod {
+        sync::spawn_sync_task(
+            fdr_cache.clone(),
+            search_backend.clone(),
+            metrics.clone(),
+            sync_status.clone(),
+            tag_normalizer.clone(),
+            transcript_store.clone(),
+        );
     }
 
-    println!("Starting server...");
+    tracing::info!("Starting server...");
     rocket::build()
         .manage(fdr_cache)
         .manage(search_backend)
+        .manage(saved_feed_store)
+        .manage(taxonomy)
+        .manage(tag_normalizer)
+        .manage(transcript_store)
+        .manage(audio_cache_or)
+        .manage(env_vars)
+        .manage(sync_status)
+        .manage(metrics.clone())
+        .attach(MetricsFairing::new(metrics))
+        .attach(RequestTracingFairing)
         .register("/", catchers![not_found_handler])
-        .mount("/", routes![healthz_handler])
-        .mount(
-            "/api",
-            routes![
-                reset_handler,
-                get_podcast_handler,
-                search_podcasts_handler,
-                search_podcasts_as_rss_feed_handler,
-                get_filtered_tags_with_counts_handler
-            ],
-        )
+        .register("/api", api::error::catchers())
+        .mount("/", routes![healthz_handler, metrics_handler])
+        .mount("/", health::routes())
+        .mount("/api", api::legacy::routes())
+        .mount("/api/v1", api::v1::routes())
+        .mount("/api/v1", api::openapi::routes())
 }
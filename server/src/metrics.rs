@@ -0,0 +1,275 @@
+//! Prometheus metrics, exposed in the text exposition format at `/metrics`.
+
+use prometheus::{
+    Encoder, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, Opts,
+    Registry, TextEncoder,
+};
+use rocket::fairing::{Fairing, Info, Kind};
+use rocket::{Data, Request, Response};
+use std::time::{Duration, Instant};
+
+/// Handles to every metric that the server records. Cloning is cheap, and all
+/// clones record to the same underlying metrics.
+#[derive(Clone)]
+pub struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    search_cache_hits: IntCounter,
+    search_cache_misses: IntCounter,
+    search_cache_evictions: IntCounter,
+    meilisearch_request_duration: HistogramVec,
+    meilisearch_errors: IntCounterVec,
+    sync_duration: Histogram,
+    sync_failures: IntCounter,
+    last_successful_sync_time: IntGauge,
+    cached_podcasts: IntGauge,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "Number of HTTP requests handled."),
+            &["route", "method", "status"],
+        )
+        .unwrap();
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "Time taken to handle HTTP requests.",
+            ),
+            &["route", "method"],
+        )
+        .unwrap();
+        let search_cache_hits = IntCounter::new(
+            "search_cache_hits_total",
+            "Number of searches served from the search cache.",
+        )
+        .unwrap();
+        let search_cache_misses = IntCounter::new(
+            "search_cache_misses_total",
+            "Number of searches that weren't in the search cache.",
+        )
+        .unwrap();
+        let search_cache_evictions = IntCounter::new(
+            "search_cache_evictions_total",
+            "Number of results evicted from the search cache to make room for new ones.",
+        )
+        .unwrap();
+        let meilisearch_request_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "meilisearch_request_duration_seconds",
+                "Time taken by calls to Meilisearch.",
+            ),
+            &["operation"],
+        )
+        .unwrap();
+        let meilisearch_errors = IntCounterVec::new(
+            Opts::new(
+                "meilisearch_errors_total",
+                "Number of calls to Meilisearch that failed.",
+            ),
+            &["operation"],
+        )
+        .unwrap();
+        let sync_duration = Histogram::with_opts(
+            HistogramOpts::new(
+                "podcast_sync_duration_seconds",
+                "Time taken to fetch and ingest all podcasts.",
+            )
+            .buckets(vec![1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0]),
+        )
+        .unwrap();
+        let sync_failures = IntCounter::new(
+            "podcast_sync_failures_total",
+            "Number of podcast syncs that failed.",
+        )
+        .unwrap();
+        let last_successful_sync_time = IntGauge::new(
+            "podcast_last_successful_sync_timestamp_seconds",
+            "Unix timestamp of the last podcast sync that succeeded.",
+        )
+        .unwrap();
+        let cached_podcasts = IntGauge::new(
+            "fdr_cache_podcasts",
+            "Number of podcasts held in the podcast cache.",
+        )
+        .unwrap();
+
+        let registry = Registry::new();
+        registry.register(Box::new(http_requests.clone())).unwrap();
+        registry
+            .register(Box::new(http_request_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(search_cache_hits.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(search_cache_misses.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(search_cache_evictions.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(meilisearch_request_duration.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(meilisearch_errors.clone()))
+            .unwrap();
+        registry.register(Box::new(sync_duration.clone())).unwrap();
+        registry.register(Box::new(sync_failures.clone())).unwrap();
+        registry
+            .register(Box::new(last_successful_sync_time.clone()))
+            .unwrap();
+        registry
+            .register(Box::new(cached_podcasts.clone()))
+            .unwrap();
+
+        Self {
+            registry,
+            http_requests,
+            http_request_duration,
+            search_cache_hits,
+            search_cache_misses,
+            search_cache_evictions,
+            meilisearch_request_duration,
+            meilisearch_errors,
+            sync_duration,
+            sync_failures,
+            last_successful_sync_time,
+            cached_podcasts,
+        }
+    }
+
+    pub fn record_search_cache_hit(&self) {
+        self.search_cache_hits.inc();
+    }
+
+    pub fn record_search_cache_miss(&self) {
+        self.search_cache_misses.inc();
+    }
+
+    pub fn record_search_cache_eviction(&self) {
+        self.search_cache_evictions.inc();
+    }
+
+    /// Records the latency of a call to Meilisearch, and whether it failed.
+    pub fn record_meilisearch_request(&self, operation: &str, duration: Duration, is_error: bool) {
+        self.meilisearch_request_duration
+            .with_label_values(&[operation])
+            .observe(duration.as_secs_f64());
+        if is_error {
+            self.meilisearch_errors
+                .with_label_values(&[operation])
+                .inc();
+        }
+    }
+
+    pub fn record_successful_sync(&self, duration: Duration, podcast_count: usize) {
+        self.sync_duration.observe(duration.as_secs_f64());
+        self.last_successful_sync_time
+            .set(chrono::Utc::now().timestamp());
+        self.set_cached_podcast_count(podcast_count);
+    }
+
+    pub fn record_failed_sync(&self, duration: Duration) {
+        self.sync_duration.observe(duration.as_secs_f64());
+        self.sync_failures.inc();
+    }
+
+    pub fn set_cached_podcast_count(&self, podcast_count: usize) {
+        self.cached_podcasts.set(podcast_count as i64);
+    }
+
+    fn record_http_request(&self, route: &str, method: &str, status: u16, duration: Duration) {
+        self.http_requests
+            .with_label_values(&[route, method, &status.to_string()])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[route, method])
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Renders all metrics in the Prometheus text exposition format.
+    pub fn encode(&self) -> String {
+        let mut buffer = Vec::new();
+        // Encoding only fails if the writer does, and writing to a `Vec` can't fail.
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Fairing that records the count and latency of every HTTP request.
+pub struct MetricsFairing {
+    metrics: Metrics,
+}
+
+impl MetricsFairing {
+    pub fn new(metrics: Metrics) -> Self {
+        Self { metrics }
+    }
+}
+
+// Start time of a request, stored in the request's local cache.
+struct RequestStartTime(Option<Instant>);
+
+#[rocket::async_trait]
+impl Fairing for MetricsFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "Request Metrics",
+            kind: Kind::Request | Kind::Response,
+        }
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _data: &mut Data<'_>) {
+        request.local_cache(|| RequestStartTime(Some(Instant::now())));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let duration = match request.local_cache(|| RequestStartTime(None)).0 {
+            Some(start_time) => start_time.elapsed(),
+            None => return,
+        };
+        // Label by route template rather than by path so that
+        // the number of label values stays bounded.
+        let route = match request.route() {
+            Some(route) => route.uri.path(),
+            None => "unmatched",
+        };
+        self.metrics.record_http_request(
+            route,
+            request.method().as_str(),
+            response.status().code,
+            duration,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_metrics() {
+        let metrics = Metrics::new();
+        metrics.record_search_cache_hit();
+        metrics.record_meilisearch_request("search", Duration::from_millis(5), true);
+        metrics.record_http_request("/api/v1/search/podcasts", "GET", 200, Duration::ZERO);
+
+        let encoded = metrics.encode();
+        assert!(encoded.contains("search_cache_hits_total 1"));
+        assert!(encoded.contains("meilisearch_errors_total{operation=\"search\"} 1"));
+        assert!(encoded.contains(
+            "http_requests_total{method=\"GET\",route=\"/api/v1/search/podcasts\",status=\"200\"} 1"
+        ));
+    }
+}
@@ -1,17 +1,63 @@
-use crate::podcast::{Podcast, PodcastNumber, PodcastTag};
+use crate::podcast::{MediaLink, Podcast, PodcastNumber, PodcastTag};
 use std::collections::HashSet;
 
 pub fn create_mock_podcast(num: i32) -> Podcast {
     let mut tags = HashSet::new();
     tags.insert(PodcastTag::new(format!("Tag #{}", num % 100)));
 
+    // The first few podcasts of every hundred are parts of a series.
+    let title = match num % 100 {
+        part_number @ 1..=3 => format!("Mock Series #{} - Part {}", num / 100, part_number),
+        _ => format!("Podcast #{}", num),
+    };
+
     Podcast::new(
-        format!("Podcast #{}", num),
-        format!("Description of podcast #{}", num),
+        title,
+        mock_description(num),
         format!("http://example.com/podcasts/{}", num),
         num,
-        PodcastNumber::new(serde_json::Number::from(num)),
-        12341627541915 + (num as i64),
+        PodcastNumber::from_integer(num as u64),
+        // One podcast per day, starting in February 2009.
+        1234162754 + (num as i64) * 24 * 60 * 60,
         tags,
     )
+    .with_media_links(vec![MediaLink::new(
+        "video".to_string(),
+        format!("http://example.com/podcasts/{}.mp4", num),
+    )])
+}
+
+// Every tenth podcast lists chapters in its description.
+fn mock_description(num: i32) -> String {
+    let description = format!("Description of podcast #{}", num);
+    if num % 10 != 0 {
+        return description;
+    }
+    format!(
+        "<p>{}</p><ul><li>0:00 - Introduction</li><li>0:{:02} - Discussion</li></ul>",
+        description,
+        num % 50 / 2 + 1
+    )
+}
+
+/// Podcast with only the fields that tests care about set.
+#[cfg(test)]
+pub fn create_test_podcast(
+    num: &str,
+    title: &str,
+    length_in_seconds: i32,
+    create_time: i64,
+    tags: &[&str],
+) -> Podcast {
+    Podcast::new(
+        title.to_string(),
+        String::new(),
+        String::new(),
+        length_in_seconds,
+        num.parse().unwrap(),
+        create_time,
+        tags.iter()
+            .map(|tag| PodcastTag::new(tag.to_string()))
+            .collect(),
+    )
 }
@@ -0,0 +1,113 @@
+/// A single feed entry in an OPML subscription list.
+pub struct OpmlOutline {
+    text: String,
+    xml_url: String,
+}
+
+impl OpmlOutline {
+    pub fn new_rss(text: String, xml_url: String) -> Self {
+        Self { text, xml_url }
+    }
+}
+
+/// An OPML document that podcast apps can import to subscribe to many feeds at once.
+pub struct Opml {
+    title: String,
+    outlines: Vec<OpmlOutline>,
+}
+
+// TODO - Abstract this into a procedural macro along with all other Responder impl blocks in other structs.
+impl<'r> rocket::response::Responder<'r, 'static> for Opml {
+    fn respond_to(
+        self,
+        _request: &'r rocket::request::Request,
+    ) -> Result<rocket::response::Response<'static>, rocket::http::Status> {
+        let xml_string = self.to_string();
+        rocket::Response::build()
+            .header(rocket::http::ContentType::new("text", "x-opml"))
+            .header(rocket::http::Header::new(
+                "Content-Disposition",
+                "attachment; filename=\"freedomain-feeds.opml\"",
+            ))
+            .sized_body(xml_string.len(), std::io::Cursor::new(xml_string))
+            .ok()
+    }
+}
+
+impl Opml {
+    pub fn new(title: String, outlines: Vec<OpmlOutline>) -> Self {
+        Self { title, outlines }
+    }
+}
+
+impl std::fmt::Display for Opml {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        writeln!(f, "<opml version=\"2.0\">")?;
+        writeln!(f, "  <head>")?;
+        writeln!(f, "    <title>{}</title>", escape_xml(&self.title))?;
+        writeln!(f, "  </head>")?;
+        writeln!(f, "  <body>")?;
+        for outline in &self.outlines {
+            writeln!(
+                f,
+                "    <outline type=\"rss\" text=\"{0}\" title=\"{0}\" xmlUrl=\"{1}\"/>",
+                escape_xml(&outline.text),
+                escape_xml(&outline.xml_url)
+            )?;
+        }
+        writeln!(f, "  </body>")?;
+        write!(f, "</opml>")
+    }
+}
+
+fn escape_xml(raw: &str) -> String {
+    let mut escaped = String::with_capacity(raw.len());
+    for c in raw.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opml_to_string() {
+        let opml = Opml::new(
+            "Freedomain Feeds".to_string(),
+            vec![
+                OpmlOutline::new_rss(
+                    "philosophy".to_string(),
+                    "https://example.com/rss?tags=philosophy".to_string(),
+                ),
+                OpmlOutline::new_rss(
+                    "\"Q&A\" <live>".to_string(),
+                    "https://example.com/rss?tags=Q%26A&limit=1".to_string(),
+                ),
+            ],
+        );
+
+        assert_eq!(
+            opml.to_string(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<opml version=\"2.0\">
+  <head>
+    <title>Freedomain Feeds</title>
+  </head>
+  <body>
+    <outline type=\"rss\" text=\"philosophy\" title=\"philosophy\" xmlUrl=\"https://example.com/rss?tags=philosophy\"/>
+    <outline type=\"rss\" text=\"&quot;Q&amp;A&quot; &lt;live&gt;\" title=\"&quot;Q&amp;A&quot; &lt;live&gt;\" xmlUrl=\"https://example.com/rss?tags=Q%26A&amp;limit=1\"/>
+  </body>
+</opml>"
+        );
+    }
+}
@@ -1,114 +1,399 @@
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::cmp::Ordering;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
+use std::convert::TryFrom;
 use std::{
     ops::Add,
     time::{Duration, SystemTime},
 };
 
-use serde_json::Number;
+use crate::chapters::{extract_chapters, Chapter};
+use crate::description::{process_description, DescriptionTimestamp};
+use crate::series::SeriesMembership;
+use crate::tag_normalizer::{normalize_text, TagNormalizer};
+use crate::transcripts::TranscriptFormat;
 use sha2::Digest;
 use std::hash::{Hash, Hasher};
+use utoipa::ToSchema;
 
-#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
-pub struct PodcastTag(String);
+/// A tag as it's displayed, such as `Free Will`. Tags are compared by their
+/// normalized key (see `normalize_text`), so tags that are only written
+/// differently are equal.
+#[derive(Clone, Debug)]
+pub struct PodcastTag {
+    name: String,
+    key: String,
+}
 
 impl PodcastTag {
     pub fn new(tag: String) -> Self {
-        Self(tag)
+        Self {
+            key: normalize_text(&tag),
+            name: tag,
+        }
     }
 
     pub fn clone_to_string(&self) -> String {
-        self.0.to_string()
+        self.name.to_string()
     }
 
     pub fn to_string(&self) -> &str {
-        &self.0
+        &self.name
+    }
+
+    /// Normalized form of the tag, which is what tags are compared, sorted and filtered by.
+    pub fn get_key(&self) -> &str {
+        &self.key
     }
 }
 
-#[derive(Clone, Debug, Eq)]
-pub struct PodcastNumber {
-    num: Number,
+impl PartialEq for PodcastTag {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
 }
 
-// We're manually implementing Serialize so that a podcast number
-// is serialized to a number rather than an object containing a number value.
-impl Serialize for PodcastNumber {
+impl Eq for PodcastTag {}
+
+impl Hash for PodcastTag {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
+}
+
+// Only the name is serialized, since the key can be derived from it.
+impl Serialize for PodcastTag {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        self.num.serialize(serializer)
+        serializer.serialize_str(&self.name)
     }
 }
 
-// We're manually implementing Deserialize so that a podcast number
-// is deserialized from a number rather than an object containing a number value.
-impl<'de> Deserialize<'de> for PodcastNumber {
+impl<'de> Deserialize<'de> for PodcastTag {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        Ok(PodcastNumber::new(Number::deserialize(deserializer)?))
+        Ok(Self::new(String::deserialize(deserializer)?))
     }
 }
 
-impl PartialEq for PodcastNumber {
-    fn eq(&self, other: &Self) -> bool {
-        self.to_string() == other.to_string()
+/// Identifier of a podcast episode.
+///
+/// Most episodes have a number, and parts of a multi-part episode are given
+/// decimal numbers such as `1234.5`. Some special episodes have no number at
+/// all, and are instead identified by a hash of their audio link so that they
+/// don't collide with each other. Numbered episodes are ordered by number, and
+/// come before all special episodes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PodcastNumber {
+    value: PodcastNumberValue,
+}
+
+// Variant order determines the ordering of `PodcastNumber`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum PodcastNumberValue {
+    // The fractional digits never have trailing zeros, so that equal numbers
+    // are always represented the same way. Comparing digit strings
+    // lexicographically then gives the same result as comparing numerically.
+    Numbered { integer: u64, fraction: String },
+    Special { id: String },
+}
+
+// Prefix of the string form of special episode numbers.
+const SPECIAL_PODCAST_NUMBER_PREFIX: &str = "special-";
+// Number of hex characters of the audio link hash used as a special episode's ID.
+const SPECIAL_PODCAST_ID_LENGTH: usize = 12;
+
+// XML namespace of the Podcasting 2.0 tags, such as `podcast:transcript`, used in RSS feeds.
+const PODCAST_NAMESPACE_URL: &str = "https://podcastindex.org/namespace/1.0";
+
+impl PodcastNumber {
+    pub fn from_integer(integer: u64) -> Self {
+        Self {
+            value: PodcastNumberValue::Numbered {
+                integer,
+                fraction: String::new(),
+            },
+        }
+    }
+
+    /// Identifier for an episode that doesn't have a number.
+    pub fn new_special(audio_link: &str) -> Self {
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(audio_link);
+        let mut id = hex::encode(hasher.finalize());
+        id.truncate(SPECIAL_PODCAST_ID_LENGTH);
+        Self {
+            value: PodcastNumberValue::Special { id },
+        }
+    }
+
+    /// Whole part of the episode's number, or `None` for special episodes.
+    pub fn get_integer_part(&self) -> Option<u64> {
+        match &self.value {
+            PodcastNumberValue::Numbered { integer, .. } => Some(*integer),
+            PodcastNumberValue::Special { .. } => None,
+        }
+    }
+
+    /// Whether the episode's number has a fractional part, as parts of multi-part episodes do.
+    pub fn has_fraction(&self) -> bool {
+        match &self.value {
+            PodcastNumberValue::Numbered { fraction, .. } => !fraction.is_empty(),
+            PodcastNumberValue::Special { .. } => false,
+        }
+    }
+
+    /// Parses a non-negative decimal number, such as `1234` or `1234.5`.
+    fn parse_decimal(s: &str) -> Option<Self> {
+        let (integer, fraction) = s.split_once('.').unwrap_or((s, ""));
+        let is_digits = |digits: &str| digits.chars().all(|c| c.is_ascii_digit());
+        if integer.is_empty() || !is_digits(integer) || !is_digits(fraction) {
+            return None;
+        }
+        Some(Self {
+            value: PodcastNumberValue::Numbered {
+                integer: integer.parse().ok()?,
+                fraction: fraction.trim_end_matches('0').to_string(),
+            },
+        })
     }
 }
 
-impl Hash for PodcastNumber {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.to_string().hash(state);
+impl std::str::FromStr for PodcastNumber {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix(SPECIAL_PODCAST_NUMBER_PREFIX) {
+            Some(id) if !id.is_empty() && id.chars().all(|c| c.is_ascii_hexdigit()) => Ok(Self {
+                value: PodcastNumberValue::Special {
+                    id: id.to_ascii_lowercase(),
+                },
+            }),
+            Some(_) => Err(()),
+            None => Self::parse_decimal(s).ok_or(()),
+        }
     }
 }
 
-impl PartialOrd for PodcastNumber {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl std::fmt::Display for PodcastNumber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.value {
+            PodcastNumberValue::Numbered { integer, fraction } if fraction.is_empty() => {
+                write!(f, "{}", integer)
+            }
+            PodcastNumberValue::Numbered { integer, fraction } => {
+                write!(f, "{}.{}", integer, fraction)
+            }
+            PodcastNumberValue::Special { id } => {
+                write!(f, "{}{}", SPECIAL_PODCAST_NUMBER_PREFIX, id)
+            }
+        }
     }
 }
 
-impl Ord for PodcastNumber {
-    // TODO - I believe this behavior is undefined if either of the numbers is NOT representable as an f64. Let's handle this case.
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self.num.as_f64() > other.num.as_f64() {
-            return Ordering::Greater;
+// We're manually implementing Serialize so that numbered episodes are
+// serialized to a number, and special episodes to a string.
+impl Serialize for PodcastNumber {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match &self.value {
+            PodcastNumberValue::Numbered { integer, fraction } if fraction.is_empty() => {
+                serializer.serialize_u64(*integer)
+            }
+            // Decimals like 1234.5 aren't always exactly representable as an f64, but f64s are
+            // printed with the shortest digits that parse back to the same value, so the number
+            // round-trips as long as it has no more significant digits than an f64 can hold.
+            PodcastNumberValue::Numbered { .. } => {
+                serializer.serialize_f64(self.to_string().parse().unwrap())
+            }
+            PodcastNumberValue::Special { .. } => serializer.serialize_str(&self.to_string()),
         }
-        if self.num.as_f64() < other.num.as_f64() {
-            return Ordering::Less;
+    }
+}
+
+// We're manually implementing Deserialize so that a podcast number
+// is deserialized from either a number or a string.
+impl<'de> Deserialize<'de> for PodcastNumber {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct PodcastNumberVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for PodcastNumberVisitor {
+            type Value = PodcastNumber;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("a non-negative number or a special episode ID")
+            }
+
+            fn visit_u64<E: serde::de::Error>(self, value: u64) -> Result<Self::Value, E> {
+                Ok(PodcastNumber::from_integer(value))
+            }
+
+            fn visit_i64<E: serde::de::Error>(self, value: i64) -> Result<Self::Value, E> {
+                match u64::try_from(value) {
+                    Ok(value) => self.visit_u64(value),
+                    Err(_) => Err(E::invalid_value(
+                        serde::de::Unexpected::Signed(value),
+                        &self,
+                    )),
+                }
+            }
+
+            fn visit_f64<E: serde::de::Error>(self, value: f64) -> Result<Self::Value, E> {
+                value
+                    .to_string()
+                    .parse()
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Float(value), &self))
+            }
+
+            fn visit_str<E: serde::de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                value
+                    .parse()
+                    .map_err(|_| E::invalid_value(serde::de::Unexpected::Str(value), &self))
+            }
         }
-        Ordering::Equal
+
+        deserializer.deserialize_any(PodcastNumberVisitor)
     }
 }
 
-impl PodcastNumber {
-    pub fn new(num: Number) -> Self {
-        Self { num }
+/// OpenAPI schema of a serialized `PodcastNumber`.
+pub fn podcast_number_schema() -> utoipa::openapi::Schema {
+    let one_of = utoipa::openapi::OneOfBuilder::new()
+        .item(
+            utoipa::openapi::ObjectBuilder::new()
+                .schema_type(utoipa::openapi::SchemaType::Number)
+                .description(Some("Number of a numbered episode.")),
+        )
+        .item(
+            utoipa::openapi::ObjectBuilder::new()
+                .schema_type(utoipa::openapi::SchemaType::String)
+                .description(Some("ID of a special episode that has no number.")),
+        )
+        .build();
+    utoipa::openapi::Schema::OneOf(one_of)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaType {
+    Audio,
+    Video,
+    /// Anything that isn't a media file, such as a link to a video hosting site.
+    Other,
+}
+
+impl std::str::FromStr for MediaType {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "audio" => Ok(Self::Audio),
+            "video" => Ok(Self::Video),
+            "other" => Ok(Self::Other),
+            _ => Err(()),
+        }
     }
 }
 
-impl std::fmt::Display for PodcastNumber {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.num.fmt(f)
+/// A URL that a podcast can be listened to or watched at.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaLink {
+    /// Name that the upstream API gives the link, such as `audio` or `video`.
+    name: String,
+    media_type: MediaType,
+    /// Guessed from the URL's file extension, if it has a recognized one.
+    mime_type: Option<String>,
+    url: String,
+}
+
+impl MediaLink {
+    pub fn new(name: String, url: String) -> Self {
+        let extension = url
+            .split(['?', '#'])
+            .next()
+            .and_then(|path| path.rsplit_once('.'))
+            .map(|(_, extension)| extension.to_ascii_lowercase())
+            .unwrap_or_default();
+        let mime_type = match extension.as_str() {
+            "mp3" => Some("audio/mpeg"),
+            "m4a" => Some("audio/mp4"),
+            "aac" => Some("audio/aac"),
+            "ogg" => Some("audio/ogg"),
+            "opus" => Some("audio/opus"),
+            "mp4" => Some("video/mp4"),
+            "m4v" => Some("video/x-m4v"),
+            "mov" => Some("video/quicktime"),
+            "webm" => Some("video/webm"),
+            _ => None,
+        };
+        // The link's name is more reliable than its URL, which often has no extension.
+        let media_type = if name.contains("audio") {
+            MediaType::Audio
+        } else if name.contains("video") {
+            MediaType::Video
+        } else {
+            match mime_type {
+                Some(mime_type) if mime_type.starts_with("audio/") => MediaType::Audio,
+                Some(mime_type) if mime_type.starts_with("video/") => MediaType::Video,
+                _ => MediaType::Other,
+            }
+        };
+        Self {
+            name,
+            media_type,
+            mime_type: mime_type.map(str::to_string),
+            url,
+        }
     }
 }
 
 // TODO - Replace the tags HashSet with a Vec so that we can derive PartialEq and Hash.
-#[derive(Clone, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, Eq, Debug, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Podcast {
     title: String,
+    /// Plain text, with any markup removed.
     description: String,
+    /// Sanitized HTML, which is safe to embed.
+    // Defaulted so that documents indexed before this field existed can still be read.
+    #[serde(default)]
+    description_html: String,
+    /// Every link in the description.
+    #[serde(default)]
+    links: Vec<String>,
+    /// Timestamps at the start of lines in the description, such as "12:34 - Topic".
+    #[serde(default)]
+    timestamps: Vec<DescriptionTimestamp>,
+    /// Chapters built from the description's timestamps, if it has enough of them.
+    #[serde(default)]
+    chapters: Vec<Chapter>,
     audio_link: String,
+    /// Every link to the podcast, including its audio link, sorted by name.
+    // Defaulted so that documents indexed before this field existed can still be read.
+    #[serde(default)]
+    media_links: Vec<MediaLink>,
     length_in_seconds: i32,
+    #[schema(schema_with = podcast_number_schema)]
     podcast_number: PodcastNumber,
     podcast_number_hash: String, // Used as the primary key by Meilisearch, since it contains only alphanumeric characters.
     create_time: i64,
+    #[schema(value_type = Vec<String>)]
     tags: HashSet<PodcastTag>,
+    /// The series that the podcast is part of, if any.
+    #[serde(default)]
+    series: Option<SeriesMembership>,
+    /// Formats that the podcast's transcript can be fetched in, which is empty if it has no transcript.
+    #[serde(default)]
+    transcript_formats: Vec<TranscriptFormat>,
 }
 
 // TODO - Abstract this into a procedural macro along with all other Responder impl blocks in other structs.
@@ -150,44 +435,162 @@ impl Podcast {
         let mut hasher = sha2::Sha256::new();
         hasher.update(podcast_number.to_string());
         let podcast_number_hash = hex::encode(hasher.finalize());
+        let description = process_description(&description);
+        let chapters = extract_chapters(&description.timestamps, length_in_seconds);
         Self {
             title,
-            description,
+            description: description.text,
+            description_html: description.html,
+            links: description.links,
+            timestamps: description.timestamps,
+            chapters,
+            media_links: vec![MediaLink::new("audio".to_string(), audio_link.clone())],
             audio_link,
             length_in_seconds,
             podcast_number,
             podcast_number_hash,
             create_time,
             tags,
+            series: None,
+            transcript_formats: Vec::new(),
+        }
+    }
+
+    /// Replaces the podcast's media links. The audio link is always kept, even if it isn't included.
+    pub fn with_media_links(mut self, mut media_links: Vec<MediaLink>) -> Self {
+        if !media_links.iter().any(|link| link.url == self.audio_link) {
+            media_links.push(MediaLink::new("audio".to_string(), self.audio_link.clone()));
         }
+        media_links.sort_by(|link_one, link_two| link_one.name.cmp(&link_two.name));
+        self.media_links = media_links;
+        self
     }
 
-    fn to_rss_item(&self) -> rss::Item {
+    fn to_rss_item(&self, media_type: MediaType, public_base_url: &str) -> rss::Item {
         let chrono_date: chrono::DateTime<chrono::Utc> = SystemTime::UNIX_EPOCH
             .add(Duration::from_secs(self.create_time as u64))
             .into();
 
+        // Podcast apps can only play links to media files, so links without a
+        // recognized MIME type are skipped. Podcasts that don't have the
+        // requested type of media fall back to their audio.
+        let (url, mime_type) = self
+            .media_links
+            .iter()
+            .filter(|link| link.media_type == media_type)
+            .find_map(|link| Some((link.url.as_str(), link.mime_type.as_deref()?)))
+            .unwrap_or((&self.audio_link, "audio/mpeg"));
+
+        // Each transcript format gets a `podcast:transcript` tag, which podcast apps use to show captions.
+        let transcript_tags = self
+            .transcript_formats
+            .iter()
+            .map(|format| {
+                let mut attrs = BTreeMap::new();
+                attrs.insert(
+                    "url".to_string(),
+                    format!(
+                        "{}/api/v1/podcasts/{}/transcript?format={}",
+                        public_base_url, self.podcast_number, format
+                    ),
+                );
+                attrs.insert("type".to_string(), format.get_mime_type().to_string());
+                rss::extension::ExtensionBuilder::default()
+                    .name("podcast:transcript")
+                    .attrs(attrs)
+                    .build()
+            })
+            .collect::<Vec<rss::extension::Extension>>();
+        let mut extensions = rss::extension::ExtensionMap::new();
+        if !transcript_tags.is_empty() {
+            extensions
+                .entry("podcast".to_string())
+                .or_default()
+                .insert("transcript".to_string(), transcript_tags);
+        }
+
         rss::ItemBuilder::default()
             .title(self.title.clone())
-            .description(self.description.clone())
+            .description(self.description_html.clone())
             .pub_date(chrono_date.to_rfc2822())
             .enclosure(
                 rss::EnclosureBuilder::default()
-                    .url(self.audio_link.clone())
-                    .mime_type("audio/mpeg")
+                    .url(url)
+                    .mime_type(mime_type)
                     .length(format!("{}", self.length_in_seconds))
                     .build(),
             )
+            .extensions(extensions)
             .build()
     }
 
+    pub fn get_audio_link(&self) -> &str {
+        &self.audio_link
+    }
+
+    /// MIME type of the podcast's audio, which is assumed to be MP3 if it can't be told from the link.
+    pub fn get_audio_mime_type(&self) -> &str {
+        self.media_links
+            .iter()
+            .find(|link| link.url == self.audio_link)
+            .and_then(|link| link.mime_type.as_deref())
+            .unwrap_or("audio/mpeg")
+    }
+
+    pub fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn get_chapters(&self) -> &[Chapter] {
+        &self.chapters
+    }
+
+    pub fn get_length_in_seconds(&self) -> i32 {
+        self.length_in_seconds
+    }
+
     pub fn get_podcast_number(&self) -> &PodcastNumber {
         &self.podcast_number
     }
 
+    pub fn get_podcast_number_hash(&self) -> &str {
+        &self.podcast_number_hash
+    }
+
+    /// Unix timestamp (in seconds) of when the podcast was published.
+    pub fn get_create_time(&self) -> i64 {
+        self.create_time
+    }
+
     pub fn get_tags(&self) -> &HashSet<PodcastTag> {
         &self.tags
     }
+
+    pub fn get_series(&self) -> Option<&SeriesMembership> {
+        self.series.as_ref()
+    }
+
+    pub fn set_tags(&mut self, tags: HashSet<PodcastTag>) {
+        self.tags = tags;
+    }
+
+    pub fn set_series(&mut self, series: Option<SeriesMembership>) {
+        self.series = series;
+    }
+
+    pub fn set_transcript_formats(&mut self, transcript_formats: Vec<TranscriptFormat>) {
+        self.transcript_formats = transcript_formats;
+    }
+
+    /// Replaces every tag with its canonical form, dropping any tags that are empty once normalized.
+    pub fn normalize_tags(&mut self, tag_normalizer: &TagNormalizer) {
+        self.tags = self
+            .tags
+            .iter()
+            .map(|tag| tag_normalizer.normalize(tag.to_string()))
+            .filter(|tag| !tag.get_key().is_empty())
+            .collect();
+    }
 }
 
 pub struct RssFeed {
@@ -214,9 +617,9 @@ impl RssFeed {
     }
 }
 
-impl std::string::ToString for RssFeed {
-    fn to_string(&self) -> String {
-        self.channel.to_string()
+impl std::fmt::Display for RssFeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.channel.to_string())
     }
 }
 
@@ -224,8 +627,13 @@ pub fn generate_rss_feed(
     podcasts: &[Podcast],
     feed_title: &str,
     feed_description: &str,
+    media_type: MediaType,
+    public_base_url: &str,
 ) -> RssFeed {
+    let mut namespaces = BTreeMap::new();
+    namespaces.insert("podcast".to_string(), PODCAST_NAMESPACE_URL.to_string());
     let channel = rss::ChannelBuilder::default()
+        .namespaces(namespaces)
         .title(feed_title)
         .description(feed_description)
         .language("en-us".to_string())
@@ -233,10 +641,100 @@ pub fn generate_rss_feed(
         .items(
             podcasts
                 .iter()
-                .map(|podcast| podcast.to_rss_item())
+                .map(|podcast| podcast.to_rss_item(media_type, public_base_url))
                 .collect::<Vec<rss::Item>>(),
         )
         .build();
 
     RssFeed::new(channel)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_podcast_number() {
+        let parse = |s: &str| s.parse::<PodcastNumber>().unwrap();
+        let special = PodcastNumber::new_special("http://example.com/special.mp3");
+
+        let mut numbers = vec![
+            special.clone(),
+            parse("10"),
+            parse("9.5"),
+            parse("9.25"),
+            parse("9"),
+        ];
+        numbers.sort();
+        assert_eq!(
+            numbers,
+            [
+                parse("9"),
+                parse("9.25"),
+                parse("9.5"),
+                parse("10"),
+                special.clone()
+            ]
+        );
+
+        assert_eq!(parse("9.50"), parse("9.5"));
+        assert_eq!(parse("9.0"), parse("9"));
+        assert_eq!(parse("9.0").to_string(), "9");
+        assert_eq!(parse(&special.to_string()), special);
+        assert_ne!(
+            special,
+            PodcastNumber::new_special("http://example.com/other.mp3")
+        );
+        for invalid in ["", "-1", "1e3", ".5", "special-", "special-xyz"] {
+            assert!(invalid.parse::<PodcastNumber>().is_err(), "{}", invalid);
+        }
+
+        for (number, json) in [
+            (parse("1234"), "1234"),
+            (parse("1234.5"), "1234.5"),
+            (parse("1234.1"), "1234.1"),
+            (special.clone(), &format!("\"{}\"", special)),
+        ] {
+            assert_eq!(serde_json::to_string(&number).unwrap(), json);
+            assert_eq!(serde_json::from_str::<PodcastNumber>(json).unwrap(), number);
+        }
+        assert!(serde_json::from_str::<PodcastNumber>("-1").is_err());
+    }
+
+    #[test]
+    fn test_media_links() {
+        let video = MediaLink::new("video".to_string(), "http://a.com/1.MP4?t=1".to_string());
+        assert_eq!(video.media_type, MediaType::Video);
+        assert_eq!(video.mime_type.as_deref(), Some("video/mp4"));
+        let audio = MediaLink::new("audio".to_string(), "http://a.com/1".to_string());
+        assert_eq!(audio.media_type, MediaType::Audio);
+        assert_eq!(audio.mime_type, None);
+        let other = MediaLink::new("youtube".to_string(), "http://youtu.be/1".to_string());
+        assert_eq!(other.media_type, MediaType::Other);
+
+        let podcast = Podcast::new(
+            String::new(),
+            String::new(),
+            "http://a.com/1".to_string(),
+            0,
+            PodcastNumber::from_integer(1),
+            0,
+            HashSet::new(),
+        )
+        .with_media_links(vec![other, video]);
+        assert_eq!(podcast.media_links.len(), 3);
+        assert_eq!(podcast.media_links[0].name, "audio");
+
+        let enclosure_url = |media_type: MediaType| {
+            podcast
+                .to_rss_item(media_type, "")
+                .enclosure()
+                .unwrap()
+                .url()
+                .to_string()
+        };
+        assert_eq!(enclosure_url(MediaType::Audio), "http://a.com/1");
+        assert_eq!(enclosure_url(MediaType::Video), "http://a.com/1.MP4?t=1");
+        assert_eq!(enclosure_url(MediaType::Other), "http://a.com/1");
+    }
+}
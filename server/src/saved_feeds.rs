@@ -0,0 +1,333 @@
+use crate::podcast::PodcastTag;
+use crate::search::SearchQuery;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
+
+// Number of hex characters of the definition hash used as a feed's ID.
+const FEED_ID_LENGTH: usize = 12;
+
+/// The search parameters that a saved feed is generated from.
+///
+/// This is stored independently of the HTTP API's parameter names so that
+/// renaming query params doesn't break URLs for feeds that were already saved.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedDefinition {
+    query: Option<String>,
+    #[schema(value_type = Vec<String>)]
+    tags: Vec<PodcastTag>,
+    // Omitted when empty so that the IDs of feeds saved before this was
+    // added don't change.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<String>)]
+    any_tags: Vec<PodcastTag>,
+    min_length_seconds: Option<usize>,
+    max_length_seconds: Option<usize>,
+}
+
+impl FeedDefinition {
+    pub fn new(
+        query: Option<String>,
+        tags: Vec<PodcastTag>,
+        min_length_seconds: Option<usize>,
+        max_length_seconds: Option<usize>,
+    ) -> Self {
+        Self {
+            query: query.filter(|query| !query.is_empty()),
+            tags: Self::sort_tags(tags),
+            any_tags: Vec::new(),
+            min_length_seconds,
+            max_length_seconds,
+        }
+    }
+
+    pub fn from_search_query(search_query: &SearchQuery) -> Self {
+        Self {
+            any_tags: Self::sort_tags(search_query.get_any_tags().to_vec()),
+            ..Self::new(
+                search_query.get_query().clone(),
+                search_query.get_tags().to_vec(),
+                search_query.get_min_length_seconds(),
+                search_query.get_max_length_seconds(),
+            )
+        }
+    }
+
+    pub fn to_search_query(&self) -> SearchQuery {
+        SearchQuery::new(
+            self.query.clone(),
+            self.tags.clone(),
+            self.min_length_seconds,
+            self.max_length_seconds,
+        )
+        .with_any_tags(self.any_tags.clone())
+    }
+
+    // Neither the order of tags nor how they're written affects which podcasts
+    // match. Sorting them and only keeping their keys means that equivalent
+    // definitions always produce the same ID.
+    fn sort_tags(tags: Vec<PodcastTag>) -> Vec<PodcastTag> {
+        let mut tags: Vec<PodcastTag> = tags
+            .iter()
+            .map(|tag| PodcastTag::new(tag.get_key().to_string()))
+            .collect();
+        tags.sort_by(|tag_one, tag_two| tag_one.get_key().cmp(tag_two.get_key()));
+        tags.dedup();
+        tags
+    }
+
+    fn generate_id(&self) -> String {
+        let mut hasher = sha2::Sha256::new();
+        hasher.update(serde_json::json!(self).to_string());
+        let mut id = hex::encode(hasher.finalize());
+        id.truncate(FEED_ID_LENGTH);
+        id
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedFeed {
+    id: String,
+    definition: FeedDefinition,
+    create_time: i64,
+}
+
+impl SavedFeed {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn get_definition(&self) -> &FeedDefinition {
+        &self.definition
+    }
+
+    pub fn into_info(self) -> SavedFeedInfo {
+        SavedFeedInfo {
+            rss_path: format!("/api/v1/feeds/{}/rss", self.id),
+            feed: self,
+        }
+    }
+}
+
+/// A saved feed along with the path to the RSS feed generated from it.
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedFeedInfo {
+    #[serde(flatten)]
+    feed: SavedFeed,
+    rss_path: String,
+}
+
+// TODO - Abstract this into a procedural macro along with all other Responder impl blocks in other structs.
+impl<'r> rocket::response::Responder<'r, 'static> for SavedFeedInfo {
+    fn respond_to(
+        self,
+        _request: &'r rocket::request::Request,
+    ) -> Result<rocket::response::Response<'static>, rocket::http::Status> {
+        let json_string = serde_json::json!(self).to_string();
+        rocket::Response::build()
+            .header(rocket::http::ContentType::JSON)
+            .sized_body(json_string.len(), std::io::Cursor::new(json_string))
+            .ok()
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SavedFeedList {
+    pub feeds: Vec<SavedFeedInfo>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct SavedFeedsFile {
+    feeds: Vec<SavedFeed>,
+}
+
+/// Stores saved feed definitions, persisting them to a local JSON file.
+#[derive(Clone)]
+pub struct SavedFeedStore {
+    feeds_by_id: Arc<DashMap<String, SavedFeed>>,
+    file_path_or: Option<PathBuf>, // Only `None` if feeds should be kept in memory only.
+    file_lock: Arc<Mutex<()>>,
+}
+
+impl SavedFeedStore {
+    /// Loads all feeds saved in the file at the given path. The file is
+    /// created the first time a feed is saved if it doesn't exist yet.
+    pub fn new_with_file(file_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let store = Self {
+            feeds_by_id: Arc::from(DashMap::new()),
+            file_path_or: Some(file_path.to_path_buf()),
+            file_lock: Arc::from(Mutex::new(())),
+        };
+
+        if file_path.exists() {
+            let file: SavedFeedsFile = serde_json::from_slice(&std::fs::read(file_path)?)?;
+            for feed in file.feeds {
+                store.feeds_by_id.insert(feed.id.clone(), feed);
+            }
+        }
+
+        Ok(store)
+    }
+
+    pub fn new_in_memory() -> Self {
+        Self {
+            feeds_by_id: Arc::from(DashMap::new()),
+            file_path_or: None,
+            file_lock: Arc::from(Mutex::new(())),
+        }
+    }
+
+    /// Saves a feed definition and returns the resulting feed. Saving a
+    /// definition that already exists returns the existing feed.
+    pub async fn save(
+        &self,
+        definition: FeedDefinition,
+    ) -> Result<SavedFeed, Box<dyn Error + Send + Sync>> {
+        let id = definition.generate_id();
+        // Held until the feed is inserted, so that concurrent saves can't
+        // write files that are each missing the other's feed.
+        let _guard = self.file_lock.lock().await;
+        if let Some(existing_feed) = self.feeds_by_id.get(&id) {
+            return Ok(existing_feed.value().clone());
+        }
+
+        let feed = SavedFeed {
+            id: id.clone(),
+            definition,
+            create_time: chrono::Utc::now().timestamp(),
+        };
+        // The feed is only inserted once it's persisted, so that a feed that
+        // fails to save can't be resolved until the server restarts.
+        let mut feeds = self.list();
+        feeds.push(feed.clone());
+        self.write_to_file(feeds).await?;
+        self.feeds_by_id.insert(id, feed.clone());
+        Ok(feed)
+    }
+
+    pub fn get(&self, id: &str) -> Option<SavedFeed> {
+        self.feeds_by_id.get(id).map(|entry| entry.value().clone())
+    }
+
+    /// All saved feeds, oldest first.
+    pub fn list(&self) -> Vec<SavedFeed> {
+        let mut feeds: Vec<SavedFeed> = self
+            .feeds_by_id
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect();
+        feeds.sort_by(|feed_one, feed_two| {
+            feed_one
+                .create_time
+                .cmp(&feed_two.create_time)
+                .then_with(|| feed_one.id.cmp(&feed_two.id))
+        });
+        feeds
+    }
+
+    // Must be called while holding `file_lock`.
+    async fn write_to_file(
+        &self,
+        feeds: Vec<SavedFeed>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let file_path = match &self.file_path_or {
+            Some(file_path) => file_path,
+            None => return Ok(()),
+        };
+
+        let json_string = serde_json::to_string_pretty(&SavedFeedsFile { feeds })?;
+        // Writing to a temporary file first means that a crash mid-write
+        // can't leave us with a truncated file.
+        let tmp_file_path = file_path.with_extension("tmp");
+        tokio::fs::write(&tmp_file_path, json_string).await?;
+        tokio::fs::rename(&tmp_file_path, file_path).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feed_definition_id_is_stable() {
+        let definition_one = FeedDefinition::new(
+            Some("peaceful parenting".to_string()),
+            vec![
+                PodcastTag::new("foo".to_string()),
+                PodcastTag::new("bar".to_string()),
+            ],
+            Some(60),
+            None,
+        );
+        let definition_two = FeedDefinition::new(
+            Some("peaceful parenting".to_string()),
+            vec![
+                PodcastTag::new("bar".to_string()),
+                PodcastTag::new("foo".to_string()),
+                PodcastTag::new("bar".to_string()),
+            ],
+            Some(60),
+            None,
+        );
+        let definition_three = FeedDefinition::new(
+            Some("peaceful parenting".to_string()),
+            Vec::new(),
+            None,
+            None,
+        );
+
+        assert_eq!(definition_one, definition_two);
+        assert_eq!(definition_one.generate_id(), definition_two.generate_id());
+        assert_ne!(definition_one.generate_id(), definition_three.generate_id());
+        assert_eq!(definition_one.generate_id().len(), FEED_ID_LENGTH);
+    }
+
+    #[tokio::test]
+    async fn test_saved_feed_store_persists_feeds() {
+        let file_path =
+            std::env::temp_dir().join(format!("fdr-saved-feeds-test-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&file_path);
+
+        let store = SavedFeedStore::new_with_file(&file_path).unwrap();
+        let feed = store
+            .save(FeedDefinition::new(
+                Some("hello".to_string()),
+                Vec::new(),
+                None,
+                Some(3600),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(store.list().len(), 1);
+
+        let reloaded_store = SavedFeedStore::new_with_file(&file_path).unwrap();
+        let reloaded_feed = reloaded_store.get(feed.get_id()).unwrap();
+        assert_eq!(reloaded_feed.get_definition(), feed.get_definition());
+
+        std::fs::remove_file(&file_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_saved_feed_store_keeps_unsaved_feeds_out() {
+        // The parent directory doesn't exist, so writing the file fails.
+        let file_path = std::env::temp_dir()
+            .join(format!("fdr-missing-dir-{}", std::process::id()))
+            .join("saved_feeds.json");
+
+        let store = SavedFeedStore::new_with_file(&file_path).unwrap();
+        let definition = FeedDefinition::new(Some("hello".to_string()), Vec::new(), None, None);
+        let id = definition.generate_id();
+        assert!(store.save(definition).await.is_err());
+        assert!(store.get(&id).is_none());
+        assert!(store.list().is_empty());
+    }
+}
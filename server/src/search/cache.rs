@@ -1,27 +1,48 @@
 use super::meilisearch::SearchResult;
-use crate::podcast::PodcastTag;
+use super::query::SearchQuery;
+use crate::metrics::Metrics;
 use std::sync::{Arc, Mutex};
 
-type SearchLru = lru::LruCache<
-    (
-        Option<String>,
-        Vec<PodcastTag>,
-        Option<usize>,
-        Option<usize>,
-    ),
-    SearchResult,
->;
+type SearchLru = lru::LruCache<SearchCacheKey, SearchResult>;
+
+/// Identifies a page of search results, and whether it includes the parts of
+/// each hit's transcript that match the query.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SearchCacheKey {
+    search_query: SearchQuery,
+    limit: usize,
+    offset: usize,
+    with_transcript_matches: bool,
+}
+
+impl SearchCacheKey {
+    pub fn new(
+        search_query: SearchQuery,
+        limit: usize,
+        offset: usize,
+        with_transcript_matches: bool,
+    ) -> Self {
+        Self {
+            search_query,
+            limit,
+            offset,
+            with_transcript_matches,
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct SearchCache {
     lru: Arc<Mutex<SearchLru>>,
+    metrics: Metrics,
 }
 
 // TODO - Optimize the locking behavior of this struct, or possibly make it non-blocking.
 impl SearchCache {
-    pub fn new(cap: usize) -> Self {
+    pub fn new(cap: usize, metrics: Metrics) -> Self {
         Self {
             lru: Arc::from(Mutex::from(lru::LruCache::new(cap))),
+            metrics,
         }
     }
 
@@ -29,68 +50,28 @@ impl SearchCache {
         self.lru.lock().unwrap().clear();
     }
 
-    // TODO - Find a way to reduce the number of arguments so we can remove this.
-    #[allow(clippy::too_many_arguments)]
-    pub async fn search(
-        &self,
-        query_or: &Option<String>,
-        tags: &[PodcastTag],
-        limit_or: Option<usize>,
-        mut offset: usize,
-        min_length_seconds: Option<usize>,
-        max_length_seconds: Option<usize>,
-        meilisearch_backend: &super::meilisearch::MeilisearchBackend,
-    ) -> SearchResult {
-        let mut tags_vec = Vec::new();
-        tags_vec.extend_from_slice(tags);
-        {
-            let mut lru = self.lru.lock().unwrap();
-            let cached_result_or = lru.get(&(
-                query_or.clone(),
-                tags_vec.clone(),
-                min_length_seconds,
-                max_length_seconds,
-            ));
-
-            if let Some(cached_result) = cached_result_or {
-                let mut cached_result_clone = cached_result.clone();
-                if offset > 0 {
-                    if offset > cached_result_clone.hits.len() {
-                        offset = cached_result_clone.hits.len();
-                    }
-                    cached_result_clone.hits.drain(0..offset);
-                }
-                if let Some(limit) = limit_or {
-                    cached_result_clone.hits.truncate(limit);
-                };
-                return cached_result_clone;
-            };
+    pub fn get(&self, key: &SearchCacheKey) -> Option<SearchResult> {
+        match self.lru.lock().unwrap().get(key) {
+            Some(cached_result) => {
+                self.metrics.record_search_cache_hit();
+                tracing::debug!("Search cache hit");
+                Some(cached_result.clone())
+            }
+            None => {
+                self.metrics.record_search_cache_miss();
+                None
+            }
         }
+    }
 
-        let result = meilisearch_backend
-            .search(
-                query_or,
-                tags,
-                limit_or.unwrap_or(99999999),
-                offset,
-                min_length_seconds,
-                max_length_seconds,
-            )
-            .await;
-
-        if limit_or.is_none() && offset == 0 {
-            let mut lru = self.lru.lock().unwrap();
-            lru.put(
-                (
-                    query_or.clone(),
-                    tags_vec,
-                    min_length_seconds,
-                    max_length_seconds,
-                ),
-                result.clone(),
-            );
+    pub fn insert(&self, key: SearchCacheKey, result: SearchResult) {
+        let mut lru = self.lru.lock().unwrap();
+        // `push` also returns the old value when replacing an existing key,
+        // which isn't an eviction.
+        if let Some((evicted_key, _)) = lru.push(key.clone(), result) {
+            if evicted_key != key {
+                self.metrics.record_search_cache_eviction();
+            }
         }
-
-        result
     }
 }
@@ -0,0 +1,190 @@
+//! Construction of Meilisearch filter expressions.
+//!
+//! Filters are only ever built through `FilterBuilder`, so attribute names come
+//! from a fixed set and every user-supplied value is quoted and escaped. This
+//! guarantees that a value can't close its own quotes and inject operators
+//! into the filter.
+
+/// Document attributes that can be filtered on. These must also be registered
+/// as filterable attributes on the Meilisearch index.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterAttribute {
+    Tags,
+    LengthInSeconds,
+}
+
+impl FilterAttribute {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tags => "tagKeys",
+            Self::LengthInSeconds => "lengthInSeconds",
+        }
+    }
+}
+
+/// Builds a filter where every added condition must hold.
+#[derive(Default)]
+pub struct FilterBuilder {
+    conditions: Vec<String>,
+}
+
+impl FilterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn equals(mut self, attribute: FilterAttribute, value: &str) -> Self {
+        self.conditions
+            .push(format!("{} = {}", attribute.as_str(), quote_value(value)));
+        self
+    }
+
+    /// Requires the attribute to equal at least one of the values. Adds no
+    /// condition if there are no values.
+    pub fn equals_any(mut self, attribute: FilterAttribute, values: &[&str]) -> Self {
+        if !values.is_empty() {
+            let alternatives: Vec<String> = values
+                .iter()
+                .map(|value| format!("{} = {}", attribute.as_str(), quote_value(value)))
+                .collect();
+            self.conditions
+                .push(format!("({})", alternatives.join(" OR ")));
+        }
+        self
+    }
+
+    pub fn at_least(mut self, attribute: FilterAttribute, value: usize) -> Self {
+        self.conditions
+            .push(format!("{} >= {}", attribute.as_str(), value));
+        self
+    }
+
+    pub fn at_most(mut self, attribute: FilterAttribute, value: usize) -> Self {
+        self.conditions
+            .push(format!("{} <= {}", attribute.as_str(), value));
+        self
+    }
+
+    /// Returns the filter expression, or `None` if no conditions were added.
+    pub fn build(self) -> Option<String> {
+        if self.conditions.is_empty() {
+            None
+        } else {
+            Some(self.conditions.join(" AND "))
+        }
+    }
+}
+
+/// Wraps a value in double quotes, escaping any backslashes and double quotes
+/// it contains so that the value is always read back as a single string.
+fn quote_value(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Splits a filter into its tokens the way Meilisearch does, unescaping
+    /// quoted strings. Returns `None` if a quoted string is never closed.
+    fn tokenize(filter: &str) -> Option<Vec<String>> {
+        let mut tokens = Vec::new();
+        let mut chars = filter.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == ' ' {
+                continue;
+            }
+            let mut token = String::new();
+            if c == '"' {
+                loop {
+                    match chars.next()? {
+                        '\\' => token.push(chars.next()?),
+                        '"' => break,
+                        c => token.push(c),
+                    }
+                }
+            } else {
+                token.push(c);
+                while let Some(c) = chars.next_if(|c| *c != ' ') {
+                    token.push(c);
+                }
+            }
+            tokens.push(token);
+        }
+        Some(tokens)
+    }
+
+    #[test]
+    fn test_filter_builder() {
+        assert_eq!(FilterBuilder::new().build(), None);
+        assert_eq!(
+            FilterBuilder::new()
+                .equals(FilterAttribute::Tags, "foo")
+                .at_least(FilterAttribute::LengthInSeconds, 1)
+                .at_most(FilterAttribute::LengthInSeconds, 2)
+                .build()
+                .unwrap(),
+            "tagKeys = \"foo\" AND lengthInSeconds >= 1 AND lengthInSeconds <= 2"
+        );
+        assert_eq!(
+            FilterBuilder::new()
+                .equals(FilterAttribute::Tags, "a \"quoted\" \\ tag")
+                .build()
+                .unwrap(),
+            "tagKeys = \"a \\\"quoted\\\" \\\\ tag\""
+        );
+        assert_eq!(
+            FilterBuilder::new()
+                .equals_any(FilterAttribute::Tags, &[])
+                .build(),
+            None
+        );
+        assert_eq!(
+            FilterBuilder::new()
+                .equals(FilterAttribute::Tags, "foo")
+                .equals_any(FilterAttribute::Tags, &["bar", "baz"])
+                .build()
+                .unwrap(),
+            "tagKeys = \"foo\" AND (tagKeys = \"bar\" OR tagKeys = \"baz\")"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn test_tags_cannot_alter_filter(
+            tags in prop::collection::vec(
+                prop_oneof![any::<String>(), "[\"\\\\ ]*(AND|OR|NOT)?[\"\\\\ =]*"],
+                1..5
+            )
+        ) {
+            let filter = tags
+                .iter()
+                .fold(FilterBuilder::new(), |builder, tag| {
+                    builder.equals(FilterAttribute::Tags, tag)
+                })
+                .build()
+                .unwrap();
+
+            let mut expected_tokens = Vec::new();
+            for (i, tag) in tags.iter().enumerate() {
+                if i > 0 {
+                    expected_tokens.push("AND".to_string());
+                }
+                expected_tokens.push("tagKeys".to_string());
+                expected_tokens.push("=".to_string());
+                expected_tokens.push(tag.clone());
+            }
+            prop_assert_eq!(tokenize(&filter), Some(expected_tokens));
+        }
+    }
+}
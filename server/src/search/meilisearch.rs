@@ -1,33 +1,83 @@
+use super::filter::{FilterAttribute, FilterBuilder};
+use super::query::SearchQuery;
+use crate::metrics::Metrics;
 use crate::mock::create_mock_podcast;
 use crate::podcast::{Podcast, PodcastTag};
+use crate::series::detect_series;
+use crate::tag_normalizer::TagNormalizer;
+use crate::transcripts::{TranscriptMatch, TranscriptStore};
 use meilisearch_sdk::tasks::Task;
 use meilisearch_sdk::{client::Client, indexes::Index};
 use serde::Serialize;
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+// Searches that take longer than this are logged as warnings.
+const SLOW_SEARCH_THRESHOLD: Duration = Duration::from_millis(500);
+// Fields of `Podcast` that are returned with each hit. Transcripts are too
+// large to return with every hit, so they're searchable but not displayed.
+const DISPLAYED_ATTRIBUTES: [&str; 15] = [
+    "title",
+    "description",
+    "descriptionHtml",
+    "links",
+    "timestamps",
+    "chapters",
+    "audioLink",
+    "mediaLinks",
+    "lengthInSeconds",
+    "podcastNumber",
+    "podcastNumberHash",
+    "createTime",
+    "tags",
+    "series",
+    "transcriptFormats",
+];
 
 #[derive(Clone)]
 pub struct MeilisearchBackend {
     client: Arc<Client>,
     podcast_index: Arc<Index>,
+    metrics: Metrics,
 }
 
 impl MeilisearchBackend {
     pub async fn new(
         host: String,
         api_key: String,
+        metrics: Metrics,
     ) -> Result<Self, meilisearch_sdk::errors::Error> {
         let client = Client::new(host, api_key);
         let podcast_index = Self::get_wiped_podcast_index(&client).await?;
         Ok(Self {
             client: Arc::from(client),
             podcast_index: Arc::from(podcast_index),
+            metrics,
         })
     }
 
+    /// Connects to an existing index without clearing it, or even checking that
+    /// Meilisearch is reachable.
+    #[cfg(test)]
+    pub fn new_without_reset(host: String, api_key: String, metrics: Metrics) -> Self {
+        let client = Client::new(host, api_key);
+        let podcast_index = client.index("podcasts");
+        Self {
+            client: Arc::from(client),
+            podcast_index: Arc::from(podcast_index),
+            metrics,
+        }
+    }
+
     /// Clears and rebuilds the underlying Meilisearch index.
     pub async fn reset_index(&self) -> Result<(), meilisearch_sdk::errors::Error> {
-        Self::get_wiped_podcast_index(&self.client).await?;
+        let start_time = Instant::now();
+        let result = Self::get_wiped_podcast_index(&self.client).await;
+        self.metrics
+            .record_meilisearch_request("reset", start_time.elapsed(), result.is_err());
+        result?;
         Ok(())
     }
 
@@ -49,8 +99,21 @@ impl MeilisearchBackend {
             .try_make_index(client)
             .unwrap();
 
+        // Descriptions are also stored as HTML, which mustn't be searched.
+        podcast_index
+            .set_searchable_attributes([
+                "title",
+                "description",
+                "tags",
+                "chapters.title",
+                "transcript",
+            ])
+            .await?;
+        podcast_index
+            .set_displayed_attributes(DISPLAYED_ATTRIBUTES)
+            .await?;
         podcast_index
-            .set_filterable_attributes(["tags", "lengthInSeconds"])
+            .set_filterable_attributes(["tagKeys", "lengthInSeconds"])
             .await?;
         podcast_index
             .set_sortable_attributes(["podcastNumber"])
@@ -59,36 +122,69 @@ impl MeilisearchBackend {
         Ok(podcast_index)
     }
 
+    /// Number of podcasts in the search index, which fails if Meilisearch isn't reachable.
+    pub async fn get_indexed_podcast_count(&self) -> Result<usize, meilisearch_sdk::errors::Error> {
+        self.client.health().await?;
+        Ok(self.podcast_index.get_stats().await?.number_of_documents)
+    }
+
     pub async fn search(
         &self,
-        query_or: &Option<String>,
-        tags: &[PodcastTag],
+        search_query: &SearchQuery,
         limit: usize,
         offset: usize,
-        min_length_seconds: Option<usize>,
-        max_length_seconds: Option<usize>,
-    ) -> SearchResult {
+    ) -> Result<SearchResult, meilisearch_sdk::errors::Error> {
         let mut search_request = self.podcast_index.search();
 
-        let filter = Self::create_meilisearch_filter(tags, min_length_seconds, max_length_seconds);
-        if !filter.is_empty() {
-            search_request.with_filter(&filter);
+        let filter_or = Self::create_meilisearch_filter(
+            search_query.get_tags(),
+            search_query.get_any_tags(),
+            search_query.get_min_length_seconds(),
+            search_query.get_max_length_seconds(),
+        );
+        if let Some(filter) = &filter_or {
+            search_request.with_filter(filter);
         }
 
         search_request.with_sort(&["podcastNumber:desc"]);
 
-        match query_or {
-            Some(query) => {
-                search_request.with_query(query);
-            }
-            None => {}
-        };
+        if let Some(query) = search_query.get_query() {
+            search_request.with_query(query);
+        }
 
         search_request.with_offset(offset).with_limit(limit);
 
-        let results = search_request.execute::<Podcast>().await.unwrap();
+        let start_time = Instant::now();
+        let results_or = search_request.execute::<Podcast>().await;
+        let duration = start_time.elapsed();
+        self.metrics
+            .record_meilisearch_request("search", duration, results_or.is_err());
+        let results = match results_or {
+            Ok(results) => results,
+            Err(err) => {
+                tracing::error!(
+                    error = %err,
+                    duration_ms = duration.as_millis() as u64,
+                    "Meilisearch search failed"
+                );
+                return Err(err);
+            }
+        };
+        if duration > SLOW_SEARCH_THRESHOLD {
+            tracing::warn!(
+                filter = ?filter_or,
+                duration_ms = duration.as_millis() as u64,
+                "Slow Meilisearch search"
+            );
+        } else {
+            tracing::debug!(
+                filter = ?filter_or,
+                duration_ms = duration.as_millis() as u64,
+                "Meilisearch search completed"
+            );
+        }
 
-        SearchResult {
+        Ok(SearchResult {
             hits: results
                 .hits
                 .into_iter()
@@ -97,54 +193,105 @@ impl MeilisearchBackend {
             total_hits: results.estimated_total_hits,
             total_hits_is_approximate: true,
             processing_time_ms: results.processing_time_ms,
-        }
+            transcript_matches: HashMap::new(),
+        })
     }
 
     fn create_meilisearch_filter(
         tags: &[PodcastTag],
+        any_tags: &[PodcastTag],
         min_length_seconds: Option<usize>,
         max_length_seconds: Option<usize>,
-    ) -> String {
-        let mut filter_elements: Vec<String> = Vec::new();
-
-        if !tags.is_empty() {
-            filter_elements.push(
-                tags.iter()
-                    .map(|tag| format!("tags = \"{}\"", tag.clone_to_string()))
-                    .collect::<Vec<String>>()
-                    .join(" AND "),
-            );
+    ) -> Option<String> {
+        let mut filter_builder = FilterBuilder::new();
+
+        for tag in tags {
+            filter_builder = filter_builder.equals(FilterAttribute::Tags, tag.get_key());
         }
 
+        let any_tags: Vec<&str> = any_tags.iter().map(PodcastTag::get_key).collect();
+        filter_builder = filter_builder.equals_any(FilterAttribute::Tags, &any_tags);
+
         if let Some(min_length_seconds) = min_length_seconds {
-            filter_elements.push(format!("lengthInSeconds > {}", min_length_seconds - 1));
+            filter_builder =
+                filter_builder.at_least(FilterAttribute::LengthInSeconds, min_length_seconds);
         }
 
         if let Some(max_length_seconds) = max_length_seconds {
-            filter_elements.push(format!("lengthInSeconds < {}", max_length_seconds + 1));
+            filter_builder =
+                filter_builder.at_most(FilterAttribute::LengthInSeconds, max_length_seconds);
         }
 
-        filter_elements.join(" AND ")
+        filter_builder.build()
     }
 
-    pub async fn ingest_podcasts_or_panic(&self, podcasts: impl Iterator<Item = &Podcast>) {
-        let mut cloned_podcasts: Vec<Podcast> = podcasts.cloned().collect();
+    pub async fn ingest_podcasts<'a>(
+        &self,
+        podcasts: impl Iterator<Item = &'a Podcast>,
+        transcript_store: &TranscriptStore,
+    ) -> Result<(), String> {
+        let mut indexed_podcasts: Vec<IndexedPodcast> = podcasts
+            .map(|podcast| IndexedPodcast {
+                tag_keys: podcast.get_tags().iter().map(PodcastTag::get_key).collect(),
+                transcript: transcript_store
+                    .get(podcast.get_podcast_number())
+                    .map(|transcript| transcript.get_text()),
+                podcast,
+            })
+            .collect();
         // Since the first items that are indexed have highest priority, reversing
         // the order ensures that the latest podcasts are returned first.
-        cloned_podcasts.reverse();
-        let task = self
+        indexed_podcasts.reverse();
+        let start_time = Instant::now();
+        let task_or = match self
             .podcast_index
-            .add_documents(&cloned_podcasts, Some("podcastNumberHash"))
-            .await
-            .unwrap()
-            .wait_for_completion(&self.client, None, Some(Duration::from_secs(60)))
+            .add_documents(&indexed_podcasts, Some("podcastNumberHash"))
             .await
-            .unwrap();
-        assert!(matches!(task, Task::Succeeded { .. }));
+        {
+            Ok(task) => {
+                task.wait_for_completion(&self.client, None, Some(Duration::from_secs(60)))
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+        let error_or = match task_or {
+            Ok(Task::Succeeded { .. }) => None,
+            Ok(Task::Failed { content }) => Some(content.error.error_message),
+            Ok(_) => Some("Ingestion task didn't finish in time".to_string()),
+            Err(err) => Some(err.to_string()),
+        };
+        self.metrics
+            .record_meilisearch_request("ingest", start_time.elapsed(), error_or.is_some());
+        match error_or {
+            None => {
+                tracing::info!(
+                    podcast_count = indexed_podcasts.len(),
+                    duration_ms = start_time.elapsed().as_millis() as u64,
+                    "Ingested podcasts into Meilisearch"
+                );
+                Ok(())
+            }
+            Some(error) => {
+                tracing::error!(error = %error, "Failed to ingest podcasts into Meilisearch");
+                Err(error)
+            }
+        }
     }
 }
 
-#[derive(Serialize, Clone)]
+/// A podcast as it's stored in the search index, along with its transcript.
+#[derive(Serialize)]
+struct IndexedPodcast<'a> {
+    #[serde(flatten)]
+    podcast: &'a Podcast,
+    // Tags are filtered by their keys, since Meilisearch doesn't case fold them.
+    #[serde(rename = "tagKeys")]
+    tag_keys: Vec<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transcript: Option<String>,
+}
+
+#[derive(Serialize, Clone, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResult {
     pub hits: Vec<Podcast>,
@@ -152,6 +299,9 @@ pub struct SearchResult {
     // TODO - Remove this field. It is always true.
     total_hits_is_approximate: bool,
     processing_time_ms: usize,
+    /// Parts of each hit's transcript that match the query, keyed by podcast
+    /// number. Podcasts without any matches are left out.
+    transcript_matches: HashMap<String, Vec<TranscriptMatch>>,
 }
 
 // TODO - Abstract this into a procedural macro along with all other Responder impl blocks in other structs.
@@ -176,19 +326,39 @@ impl SearchResult {
     pub fn take_hits(self) -> Vec<Podcast> {
         self.hits
     }
+
+    /// Drops every hit that isn't in the page starting at `offset`.
+    pub fn paginate(&mut self, limit: usize, offset: usize) {
+        self.hits.drain(0..offset.min(self.hits.len()));
+        self.hits.truncate(limit);
+    }
+
+    pub fn set_transcript_matches(
+        &mut self,
+        transcript_matches: HashMap<String, Vec<TranscriptMatch>>,
+    ) {
+        self.transcript_matches = transcript_matches;
+    }
 }
 
-pub fn generate_mock_search_results() -> SearchResult {
+pub fn generate_mock_search_results(
+    tag_normalizer: &TagNormalizer,
+    transcript_store: &TranscriptStore,
+) -> SearchResult {
     let mut mock_podcasts = Vec::new();
     for i in 1..20 {
         mock_podcasts.push(create_mock_podcast(i));
     }
+    tag_normalizer.normalize_podcast_tags(&mut mock_podcasts);
+    detect_series(&mut mock_podcasts);
+    transcript_store.mark_transcribed_podcasts(&mut mock_podcasts);
     let total_hits = mock_podcasts.len();
     SearchResult {
         hits: mock_podcasts,
         total_hits,
         total_hits_is_approximate: false,
         processing_time_ms: 1234,
+        transcript_matches: HashMap::new(),
     }
 }
 
@@ -196,27 +366,52 @@ pub fn generate_mock_search_results() -> SearchResult {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_displayed_attributes_cover_podcast_fields() {
+        let podcast = serde_json::json!(create_mock_podcast(1));
+        for field in podcast.as_object().unwrap().keys() {
+            assert!(
+                DISPLAYED_ATTRIBUTES.contains(&field.as_str()),
+                "Podcast field '{}' isn't displayed",
+                field
+            );
+        }
+    }
+
     #[test]
     fn test_create_meilisearch_filter() {
         assert_eq!(
-            MeilisearchBackend::create_meilisearch_filter(&Vec::new(), None, None),
-            ""
+            MeilisearchBackend::create_meilisearch_filter(&Vec::new(), &[], None, None),
+            None
+        );
+        assert_eq!(
+            MeilisearchBackend::create_meilisearch_filter(&Vec::new(), &[], Some(1), None),
+            Some("lengthInSeconds >= 1".to_string())
         );
         assert_eq!(
-            MeilisearchBackend::create_meilisearch_filter(&Vec::new(), Some(1), None),
-            "lengthInSeconds > 0"
+            MeilisearchBackend::create_meilisearch_filter(
+                &Vec::new(),
+                &[],
+                Some(0),
+                Some(usize::MAX)
+            ),
+            Some(format!(
+                "lengthInSeconds >= 0 AND lengthInSeconds <= {}",
+                usize::MAX
+            ))
         );
         assert_eq!(
-            MeilisearchBackend::create_meilisearch_filter(&Vec::new(), Some(1), Some(2)),
-            "lengthInSeconds > 0 AND lengthInSeconds < 3"
+            MeilisearchBackend::create_meilisearch_filter(&Vec::new(), &[], Some(1), Some(2)),
+            Some("lengthInSeconds >= 1 AND lengthInSeconds <= 2".to_string())
         );
         assert_eq!(
             MeilisearchBackend::create_meilisearch_filter(
                 &[PodcastTag::new("hello world".to_string())],
+                &[],
                 None,
                 None
             ),
-            "tags = \"hello world\""
+            Some("tagKeys = \"hello world\"".to_string())
         );
         assert_eq!(
             MeilisearchBackend::create_meilisearch_filter(
@@ -224,29 +419,46 @@ mod tests {
                     PodcastTag::new("foo".to_string()),
                     PodcastTag::new("bar".to_string())
                 ],
+                &[],
                 None,
                 None
             ),
-            "tags = \"foo\" AND tags = \"bar\""
+            Some("tagKeys = \"foo\" AND tagKeys = \"bar\"".to_string())
+        );
+        assert_eq!(
+            MeilisearchBackend::create_meilisearch_filter(
+                &[PodcastTag::new("foo".to_string())],
+                &[
+                    PodcastTag::new("bar".to_string()),
+                    PodcastTag::new("baz".to_string())
+                ],
+                None,
+                None
+            ),
+            Some("tagKeys = \"foo\" AND (tagKeys = \"bar\" OR tagKeys = \"baz\")".to_string())
         );
         assert_eq!(
             MeilisearchBackend::create_meilisearch_filter(
                 &[PodcastTag::new("hello world".to_string())],
+                &[],
                 Some(1),
                 Some(2)
             ),
-            "tags = \"hello world\" AND lengthInSeconds > 0 AND lengthInSeconds < 3"
+            Some(
+                "tagKeys = \"hello world\" AND lengthInSeconds >= 1 AND lengthInSeconds <= 2"
+                    .to_string()
+            )
         );
         assert_eq!(
             MeilisearchBackend::create_meilisearch_filter(
                 &[
                     PodcastTag::new("foo".to_string()),
                     PodcastTag::new("bar".to_string())
-                ],
+                ], &[],
                 Some(1),
                 Some(2)
             ),
-            "tags = \"foo\" AND tags = \"bar\" AND lengthInSeconds > 0 AND lengthInSeconds < 3"
+            Some("tagKeys = \"foo\" AND tagKeys = \"bar\" AND lengthInSeconds >= 1 AND lengthInSeconds <= 2".to_string())
         );
     }
 }
@@ -1,77 +1,246 @@
-use crate::podcast::{Podcast, PodcastTag};
+use crate::fdr_cache::FdrCache;
+use crate::metrics::Metrics;
+use crate::podcast::{Podcast, PodcastNumber};
+use crate::tag_normalizer::TagNormalizer;
+use crate::transcripts::TranscriptStore;
+use std::collections::HashMap;
 
 mod cache;
+mod filter;
 mod meilisearch;
+mod query;
 
 pub use meilisearch::SearchResult;
+pub use query::SearchQuery;
+
+/// Maximum number of time-coded transcript matches returned per podcast.
+const TRANSCRIPT_MATCHES_PER_PODCAST: usize = 3;
+/// Most hits that a single search returns, whatever limit it asks for.
+pub const MAX_SEARCH_LIMIT: usize = 1000;
 
 #[derive(Clone)]
 pub struct SearchBackend {
     meilisearch_backend_or: Option<meilisearch::MeilisearchBackend>, // Only `None` if running in mock mode.
     search_cache: cache::SearchCache,
+    tag_normalizer: TagNormalizer,
+    transcript_store: TranscriptStore,
 }
 
 impl SearchBackend {
     pub async fn new_prod(
         meilisearch_host: String,
         meilisearch_api_key: String,
+        metrics: Metrics,
+        tag_normalizer: TagNormalizer,
+        transcript_store: TranscriptStore,
     ) -> Result<Self, meilisearch_sdk::errors::Error> {
         Ok(Self {
             meilisearch_backend_or: Some(
-                meilisearch::MeilisearchBackend::new(meilisearch_host, meilisearch_api_key).await?,
+                meilisearch::MeilisearchBackend::new(
+                    meilisearch_host,
+                    meilisearch_api_key,
+                    metrics.clone(),
+                )
+                .await?,
             ),
-            search_cache: cache::SearchCache::new(10000),
+            search_cache: cache::SearchCache::new(10000, metrics),
+            tag_normalizer,
+            transcript_store,
         })
     }
 
-    pub fn new_mock() -> Self {
+    #[cfg(test)]
+    pub fn new_prod_without_reset(
+        meilisearch_host: String,
+        meilisearch_api_key: String,
+        metrics: Metrics,
+        tag_normalizer: TagNormalizer,
+        transcript_store: TranscriptStore,
+    ) -> Self {
+        Self {
+            meilisearch_backend_or: Some(meilisearch::MeilisearchBackend::new_without_reset(
+                meilisearch_host,
+                meilisearch_api_key,
+                metrics.clone(),
+            )),
+            search_cache: cache::SearchCache::new(10000, metrics),
+            tag_normalizer,
+            transcript_store,
+        }
+    }
+
+    pub fn new_mock(
+        metrics: Metrics,
+        tag_normalizer: TagNormalizer,
+        transcript_store: TranscriptStore,
+    ) -> Self {
         Self {
             meilisearch_backend_or: None,
-            search_cache: cache::SearchCache::new(0),
+            search_cache: cache::SearchCache::new(0, metrics),
+            tag_normalizer,
+            transcript_store,
         }
     }
 
-    pub async fn reset(&self) {
+    pub async fn reset(&self) -> Result<(), meilisearch_sdk::errors::Error> {
         if let Some(meilisearch_backend) = &self.meilisearch_backend_or {
-            meilisearch_backend.reset_index().await.unwrap();
+            meilisearch_backend.reset_index().await?;
         }
 
         self.search_cache.reset();
+        Ok(())
     }
 
+    /// Number of podcasts that can be searched over. In mock mode this is
+    /// always the number of mock search results.
+    pub async fn get_indexed_podcast_count(&self) -> Result<usize, meilisearch_sdk::errors::Error> {
+        match &self.meilisearch_backend_or {
+            Some(meilisearch_backend) => meilisearch_backend.get_indexed_podcast_count().await,
+            None => Ok(meilisearch::generate_mock_search_results(
+                &self.tag_normalizer,
+                &self.transcript_store,
+            )
+            .hits
+            .len()),
+        }
+    }
+
+    /// Searches for podcasts matching the query. Returns up to `limit` (but
+    /// never more than `MAX_SEARCH_LIMIT`) podcasts after `offset`, along with
+    /// the parts of their transcripts that match the query.
+    #[tracing::instrument(skip(self))]
     pub async fn search(
         &self,
-        query_or: &Option<String>,
-        tags: &[PodcastTag],
-        limit_or: Option<usize>,
+        search_query: &SearchQuery,
+        limit: usize,
         offset: usize,
-        min_length_seconds: Option<usize>,
-        max_length_seconds: Option<usize>,
-    ) -> SearchResult {
-        match &self.meilisearch_backend_or {
-            Some(meilisearch_backend) => {
-                self.search_cache
-                    .search(
-                        query_or,
-                        tags,
-                        limit_or,
-                        offset,
-                        min_length_seconds,
-                        max_length_seconds,
-                        meilisearch_backend,
-                    )
-                    .await
+    ) -> Result<SearchResult, meilisearch_sdk::errors::Error> {
+        self.search_page(search_query, limit, offset, true).await
+    }
+
+    // Like `search`, but only finds transcript matches if asked to, since
+    // callers that don't return the hits to the client don't need them.
+    async fn search_page(
+        &self,
+        search_query: &SearchQuery,
+        limit: usize,
+        offset: usize,
+        with_transcript_matches: bool,
+    ) -> Result<SearchResult, meilisearch_sdk::errors::Error> {
+        let limit = limit.min(MAX_SEARCH_LIMIT);
+        let meilisearch_backend = match &self.meilisearch_backend_or {
+            Some(meilisearch_backend) => meilisearch_backend,
+            None => {
+                let mut search_result = meilisearch::generate_mock_search_results(
+                    &self.tag_normalizer,
+                    &self.transcript_store,
+                );
+                search_result.paginate(limit, offset);
+                if with_transcript_matches {
+                    self.add_transcript_matches(search_query, &mut search_result)
+                        .await;
+                }
+                return Ok(search_result);
             }
-            None => meilisearch::generate_mock_search_results(),
+        };
+
+        let search_query = search_query.normalize_tags(&self.tag_normalizer);
+        let cache_key = cache::SearchCacheKey::new(
+            search_query.clone(),
+            limit,
+            offset,
+            with_transcript_matches,
+        );
+        if let Some(search_result) = self.search_cache.get(&cache_key) {
+            return Ok(search_result);
+        }
+
+        let mut search_result = meilisearch_backend
+            .search(&search_query, limit, offset)
+            .await?;
+        if with_transcript_matches {
+            self.add_transcript_matches(&search_query, &mut search_result)
+                .await;
+        }
+        self.search_cache.insert(cache_key, search_result.clone());
+        Ok(search_result)
+    }
+
+    // Finds the parts of each hit's transcript that match the query. This
+    // scans whole transcripts, so it's done off of the async executor.
+    async fn add_transcript_matches(
+        &self,
+        search_query: &SearchQuery,
+        search_result: &mut SearchResult,
+    ) {
+        let query = match search_query.get_query() {
+            Some(query) => query.clone(),
+            None => return,
+        };
+        let transcript_store = self.transcript_store.clone();
+        let podcast_numbers: Vec<PodcastNumber> = search_result
+            .get_hits()
+            .iter()
+            .map(|podcast| podcast.get_podcast_number().clone())
+            .collect();
+        let transcript_matches = tokio::task::spawn_blocking(move || {
+            let mut transcript_matches = HashMap::new();
+            for podcast_number in podcast_numbers {
+                if let Some(transcript) = transcript_store.get(&podcast_number) {
+                    let matches = transcript.find_matches(&query, TRANSCRIPT_MATCHES_PER_PODCAST);
+                    if !matches.is_empty() {
+                        transcript_matches.insert(podcast_number.to_string(), matches);
+                    }
+                }
+            }
+            transcript_matches
+        })
+        .await
+        .unwrap();
+        search_result.set_transcript_matches(transcript_matches);
+    }
+
+    /// Every podcast matching the query, newest first. Queries without text are
+    /// answered from the cache, but the search index is needed to match text,
+    /// so only the newest `MAX_SEARCH_LIMIT` matches are returned for those.
+    pub async fn search_all(
+        &self,
+        search_query: &SearchQuery,
+        fdr_cache: &FdrCache,
+    ) -> Result<Vec<Podcast>, meilisearch_sdk::errors::Error> {
+        if search_query.get_query().is_some() {
+            return Ok(self
+                .search_page(search_query, MAX_SEARCH_LIMIT, 0, false)
+                .await?
+                .take_hits());
         }
+
+        let search_query = search_query.normalize_tags(&self.tag_normalizer);
+        let mut podcasts: Vec<Podcast> = fdr_cache
+            .iter()
+            .filter(|podcast| search_query.matches_filters(podcast))
+            .cloned()
+            .collect();
+        podcasts.sort_by(|podcast_one, podcast_two| {
+            podcast_two
+                .get_podcast_number()
+                .cmp(podcast_one.get_podcast_number())
+        });
+        Ok(podcasts)
     }
 
-    pub async fn ingest_podcasts_or_panic(&self, podcasts: impl Iterator<Item = &Podcast>) {
+    #[tracing::instrument(skip_all)]
+    pub async fn ingest_podcasts(
+        &self,
+        podcasts: impl Iterator<Item = &Podcast>,
+    ) -> Result<(), String> {
         match &self.meilisearch_backend_or {
             Some(meilisearch_backend) => {
-                meilisearch_backend.ingest_podcasts_or_panic(podcasts).await;
+                meilisearch_backend
+                    .ingest_podcasts(podcasts, &self.transcript_store)
+                    .await
             }
-            None => {}
-        };
+            None => Ok(()),
+        }
     }
 }
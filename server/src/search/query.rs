@@ -0,0 +1,140 @@
+use crate::podcast::{Podcast, PodcastTag};
+use crate::tag_normalizer::TagNormalizer;
+
+/// Parameters that narrow down which podcasts a search matches.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SearchQuery {
+    query_or: Option<String>,
+    tags: Vec<PodcastTag>,
+    any_tags: Vec<PodcastTag>,
+    min_length_seconds: Option<usize>,
+    max_length_seconds: Option<usize>,
+}
+
+impl SearchQuery {
+    pub fn new(
+        query_or: Option<String>,
+        tags: Vec<PodcastTag>,
+        min_length_seconds: Option<usize>,
+        max_length_seconds: Option<usize>,
+    ) -> Self {
+        Self {
+            query_or: query_or.filter(|query| !query.is_empty()),
+            tags,
+            any_tags: Vec::new(),
+            min_length_seconds,
+            max_length_seconds,
+        }
+    }
+
+    /// Additionally requires podcasts to have at least one of the given tags,
+    /// such as the tags that make up a category.
+    pub fn with_any_tags(mut self, any_tags: Vec<PodcastTag>) -> Self {
+        self.any_tags = any_tags;
+        self
+    }
+
+    /// Replaces every tag with its canonical form, so that searching for an
+    /// alias of a tag matches podcasts with the tag itself.
+    pub fn normalize_tags(&self, tag_normalizer: &TagNormalizer) -> Self {
+        let normalize = |tags: &[PodcastTag]| {
+            tags.iter()
+                .map(|tag| tag_normalizer.normalize(tag.to_string()))
+                .collect()
+        };
+        Self {
+            tags: normalize(&self.tags),
+            any_tags: normalize(&self.any_tags),
+            ..self.clone()
+        }
+    }
+
+    /// Whether the podcast passes the query's filters. The query's text isn't
+    /// checked, since only the search index can match it.
+    pub fn matches_filters(&self, podcast: &Podcast) -> bool {
+        let tags = podcast.get_tags();
+        let length_in_seconds = podcast.get_length_in_seconds() as i64;
+        let too_short = matches!(self.min_length_seconds, Some(min_length_seconds) if length_in_seconds < min_length_seconds as i64);
+        let too_long = matches!(self.max_length_seconds, Some(max_length_seconds) if length_in_seconds > max_length_seconds as i64);
+        self.tags.iter().all(|tag| tags.contains(tag))
+            && (self.any_tags.is_empty() || self.any_tags.iter().any(|tag| tags.contains(tag)))
+            && !too_short
+            && !too_long
+    }
+
+    pub fn get_query(&self) -> &Option<String> {
+        &self.query_or
+    }
+
+    pub fn get_tags(&self) -> &[PodcastTag] {
+        &self.tags
+    }
+
+    pub fn get_any_tags(&self) -> &[PodcastTag] {
+        &self.any_tags
+    }
+
+    pub fn get_min_length_seconds(&self) -> Option<usize> {
+        self.min_length_seconds
+    }
+
+    pub fn get_max_length_seconds(&self) -> Option<usize> {
+        self.max_length_seconds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::create_test_podcast;
+
+    #[test]
+    fn test_matches_filters() {
+        let podcast = create_test_podcast("1", "", 600, 0, &["Free Will", "anarchy"]);
+        let tags = |tags: &[&str]| -> Vec<PodcastTag> {
+            tags.iter()
+                .map(|tag| PodcastTag::new(tag.to_string()))
+                .collect()
+        };
+        let matches = |query: SearchQuery| query.matches_filters(&podcast);
+
+        assert!(matches(SearchQuery::default()));
+        // The text is left to the search index.
+        assert!(matches(SearchQuery::new(
+            Some("nothing like the title".to_string()),
+            Vec::new(),
+            None,
+            None
+        )));
+        assert!(matches(SearchQuery::new(
+            None,
+            tags(&["free will", "Anarchy"]),
+            Some(600),
+            Some(600)
+        )));
+        assert!(!matches(SearchQuery::new(
+            None,
+            tags(&["free will", "freedom"]),
+            None,
+            None
+        )));
+        assert!(!matches(SearchQuery::new(
+            None,
+            Vec::new(),
+            Some(601),
+            None
+        )));
+        assert!(!matches(SearchQuery::new(
+            None,
+            Vec::new(),
+            None,
+            Some(599)
+        )));
+        assert!(matches(
+            SearchQuery::default().with_any_tags(tags(&["freedom", "anarchy"]))
+        ));
+        assert!(!matches(
+            SearchQuery::default().with_any_tags(tags(&["freedom"]))
+        ));
+    }
+}
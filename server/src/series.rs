@@ -0,0 +1,334 @@
+//! Detection of episodes that are parts of a series.
+//!
+//! Series aren't marked upstream, so they're detected as podcasts are ingested.
+//! Podcasts whose titles only differ by a part number (such as "Peaceful
+//! Parenting - Part 2" or "Peaceful Parenting (Pt. II)") form a series, as do
+//! podcasts that share the whole part of a decimal episode number (such as
+//! 1234, 1234.5 and 1234.6).
+
+use crate::podcast::{podcast_number_schema, Podcast, PodcastNumber};
+use crate::tag_normalizer::normalize_text;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+// Words that introduce a part number in a title.
+const PART_KEYWORDS: [&str; 3] = ["part", "pt", "pt."];
+// Lowercase roman numerals from 1 to 20, which some titles use for part numbers.
+const ROMAN_NUMERALS: [&str; 20] = [
+    "i", "ii", "iii", "iv", "v", "vi", "vii", "viii", "ix", "x", "xi", "xii", "xiii", "xiv", "xv",
+    "xvi", "xvii", "xviii", "xix", "xx",
+];
+
+/// The series that a podcast is part of.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesMembership {
+    /// ID of the series, which can be used to look up all of its parts.
+    id: String,
+    title: String,
+    part_number: u32,
+    part_count: usize,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SeriesPart {
+    part_number: u32,
+    #[schema(schema_with = podcast_number_schema)]
+    podcast_number: PodcastNumber,
+    title: String,
+    /// Unix timestamp (in seconds) of when the part was published.
+    create_time: i64,
+    length_in_seconds: i32,
+}
+
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Series {
+    id: String,
+    title: String,
+    /// Every part of the series, in order.
+    parts: Vec<SeriesPart>,
+}
+
+/// Every series in the podcast cache, keyed by ID.
+#[derive(Default)]
+pub struct SeriesIndex {
+    series_by_id: HashMap<String, Series>,
+}
+
+impl SeriesIndex {
+    /// Groups podcasts by the series they were assigned to by `detect_series`.
+    pub fn new<'a>(podcasts: impl Iterator<Item = &'a Podcast>) -> Self {
+        let mut series_by_id: HashMap<String, Series> = HashMap::new();
+        for podcast in podcasts {
+            if let Some(membership) = podcast.get_series() {
+                series_by_id
+                    .entry(membership.id.clone())
+                    .or_insert_with(|| Series {
+                        id: membership.id.clone(),
+                        title: membership.title.clone(),
+                        parts: Vec::new(),
+                    })
+                    .parts
+                    .push(SeriesPart {
+                        part_number: membership.part_number,
+                        podcast_number: podcast.get_podcast_number().clone(),
+                        title: podcast.get_title().to_string(),
+                        create_time: podcast.get_create_time(),
+                        length_in_seconds: podcast.get_length_in_seconds(),
+                    });
+            }
+        }
+        for series in series_by_id.values_mut() {
+            series.parts.sort_by(|part_one, part_two| {
+                (part_one.part_number, &part_one.podcast_number)
+                    .cmp(&(part_two.part_number, &part_two.podcast_number))
+            });
+        }
+        Self { series_by_id }
+    }
+
+    pub fn get_series(&self, id: &str) -> Option<&Series> {
+        self.series_by_id.get(id)
+    }
+}
+
+// A series found by `detect_series`, before it's recorded on its podcasts.
+struct DetectedSeries {
+    id: String,
+    title: String,
+    // Indices of the series' podcasts, along with their part numbers.
+    parts: Vec<(usize, u32)>,
+}
+
+/// Works out which series each podcast belongs to, if any, and records it on the podcast.
+pub fn detect_series(podcasts: &mut [Podcast]) {
+    let mut series: Vec<DetectedSeries> = Vec::new();
+
+    let mut title_parts_by_slug: HashMap<String, (String, Vec<(usize, u32)>)> = HashMap::new();
+    for (i, podcast) in podcasts.iter().enumerate() {
+        if let Some((base_title, part_number)) = parse_part_title(podcast.get_title()) {
+            title_parts_by_slug
+                .entry(slugify(base_title))
+                .or_insert_with(|| (base_title.to_string(), Vec::new()))
+                .1
+                .push((i, part_number));
+        }
+    }
+    let mut slugs: Vec<&String> = title_parts_by_slug.keys().collect();
+    slugs.sort();
+    for slug in slugs {
+        let (base_title, parts) = &title_parts_by_slug[slug];
+        let mut parts = parts.clone();
+        parts.sort_by_key(|(i, _)| (podcasts[*i].get_create_time(), *i));
+
+        // Generic titles such as "Call In Show" are reused by unrelated series,
+        // so a new series starts whenever a part number is repeated.
+        let mut runs: Vec<Vec<(usize, u32)>> = vec![Vec::new()];
+        for part in parts {
+            let run = runs.last_mut().unwrap();
+            if run.iter().any(|(_, part_number)| *part_number == part.1) {
+                runs.push(vec![part]);
+            } else {
+                run.push(part);
+            }
+        }
+        let mut run_count = 0;
+        for run in runs.into_iter().filter(|run| run.len() > 1) {
+            run_count += 1;
+            let id = match run_count {
+                1 => slug.clone(),
+                _ => format!("{}-{}", slug, run_count),
+            };
+            series.push(DetectedSeries {
+                id,
+                title: base_title.clone(),
+                parts: run,
+            });
+        }
+    }
+
+    let mut is_in_title_series = vec![false; podcasts.len()];
+    for detected_series in &series {
+        for (i, _) in &detected_series.parts {
+            is_in_title_series[*i] = true;
+        }
+    }
+    let mut numbered_parts_by_integer: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (i, podcast) in podcasts.iter().enumerate() {
+        if let Some(integer) = podcast.get_podcast_number().get_integer_part() {
+            if !is_in_title_series[i] {
+                numbered_parts_by_integer
+                    .entry(integer)
+                    .or_default()
+                    .push(i);
+            }
+        }
+    }
+    let mut integers: Vec<&u64> = numbered_parts_by_integer.keys().collect();
+    integers.sort();
+    for integer in integers {
+        let mut parts = numbered_parts_by_integer[integer].clone();
+        let has_fraction = parts
+            .iter()
+            .any(|i| podcasts[*i].get_podcast_number().has_fraction());
+        if parts.len() < 2 || !has_fraction {
+            continue;
+        }
+        parts.sort_by(|i, j| {
+            podcasts[*i]
+                .get_podcast_number()
+                .cmp(podcasts[*j].get_podcast_number())
+        });
+        series.push(DetectedSeries {
+            id: format!("episode-{}", integer),
+            title: podcasts[parts[0]].get_title().to_string(),
+            parts: parts
+                .into_iter()
+                .enumerate()
+                .map(|(position, i)| (i, position as u32 + 1))
+                .collect(),
+        });
+    }
+
+    for podcast in podcasts.iter_mut() {
+        podcast.set_series(None);
+    }
+    for detected_series in series {
+        let part_count = detected_series.parts.len();
+        for (i, part_number) in detected_series.parts {
+            podcasts[i].set_series(Some(SeriesMembership {
+                id: detected_series.id.clone(),
+                title: detected_series.title.clone(),
+                part_number,
+                part_count,
+            }));
+        }
+    }
+}
+
+/// Splits a title such as "Peaceful Parenting - Part 2" into the title of the
+/// series and the part number.
+fn parse_part_title(title: &str) -> Option<(&str, u32)> {
+    // Byte ranges of every whitespace-separated word.
+    let mut words: Vec<(usize, usize)> = Vec::new();
+    let mut word_start_or = None;
+    for (i, c) in title.char_indices() {
+        if c.is_whitespace() {
+            if let Some(word_start) = word_start_or.take() {
+                words.push((word_start, i));
+            }
+        } else if word_start_or.is_none() {
+            word_start_or = Some(i);
+        }
+    }
+    if let Some(word_start) = word_start_or {
+        words.push((word_start, title.len()));
+    }
+
+    // The last part number is used, since the series title may itself contain one.
+    for pair in words.windows(2).rev() {
+        let (keyword_start, keyword_end) = pair[0];
+        let keyword = title[keyword_start..keyword_end]
+            .trim_start_matches(|c: char| !c.is_alphanumeric())
+            .to_ascii_lowercase();
+        if !PART_KEYWORDS.contains(&keyword.as_str()) {
+            continue;
+        }
+        let (number_start, number_end) = pair[1];
+        let number = title[number_start..number_end]
+            .trim_end_matches(|c: char| !c.is_alphanumeric())
+            .to_ascii_lowercase();
+        let part_number_or = match number.parse::<u32>() {
+            Ok(part_number) => Some(part_number),
+            Err(_) => ROMAN_NUMERALS
+                .iter()
+                .position(|numeral| *numeral == number)
+                .map(|position| position as u32 + 1),
+        };
+        let base_title = title[..keyword_start]
+            .trim_end_matches(|c: char| c.is_whitespace() || "-–—:,;|([/".contains(c));
+        if let Some(part_number) = part_number_or.filter(|part_number| *part_number > 0) {
+            if base_title.chars().any(char::is_alphanumeric) {
+                return Some((base_title, part_number));
+            }
+        }
+    }
+    None
+}
+
+/// Turns a title into an ID that can be used in URLs.
+fn slugify(title: &str) -> String {
+    normalize_text(title)
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<&str>>()
+        .join("-")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::create_test_podcast;
+
+    #[test]
+    fn test_parse_part_title() {
+        assert_eq!(
+            parse_part_title("Peaceful Parenting - Part 2"),
+            Some(("Peaceful Parenting", 2))
+        );
+        assert_eq!(
+            parse_part_title("Peaceful Parenting (Pt. III)"),
+            Some(("Peaceful Parenting", 3))
+        );
+        assert_eq!(
+            parse_part_title("The Story of Your Enslavement, Part 1 of 2: Chains"),
+            Some(("The Story of Your Enslavement", 1))
+        );
+        assert_eq!(parse_part_title("Part 1"), None);
+        assert_eq!(parse_part_title("Part of the Problem"), None);
+        assert_eq!(parse_part_title("The Counterpart 2"), None);
+    }
+
+    #[test]
+    fn test_detect_series() {
+        let mut podcasts = vec![
+            create_test_podcast("1", "Call In Show - Part 2", 0, 100, &[]),
+            create_test_podcast("2", "Call in show: Part 1", 0, 0, &[]),
+            create_test_podcast("3", "Call In Show - Part 1", 0, 200, &[]),
+            create_test_podcast("4", "Call In Show - Part 2", 0, 300, &[]),
+            create_test_podcast("5", "Call In Show - Part 3", 0, 400, &[]),
+            create_test_podcast("6", "Lonely Show - Part 1", 0, 500, &[]),
+            create_test_podcast("10", "Big Interview", 0, 600, &[]),
+            create_test_podcast("10.5", "Big Interview Continued", 0, 700, &[]),
+            create_test_podcast("11", "Unrelated", 0, 800, &[]),
+        ];
+        detect_series(&mut podcasts);
+        let series_index = SeriesIndex::new(podcasts.iter());
+
+        let membership = podcasts[0].get_series().unwrap();
+        assert_eq!(membership.id, "call-in-show");
+        assert_eq!(membership.part_number, 2);
+        assert_eq!(membership.part_count, 2);
+        assert_eq!(podcasts[4].get_series().unwrap().id, "call-in-show-2");
+        assert_eq!(podcasts[4].get_series().unwrap().part_count, 3);
+        assert_eq!(podcasts[5].get_series(), None);
+        assert_eq!(podcasts[8].get_series(), None);
+
+        let parts: Vec<(u32, String)> = series_index
+            .get_series("episode-10")
+            .unwrap()
+            .parts
+            .iter()
+            .map(|part| (part.part_number, part.podcast_number.to_string()))
+            .collect();
+        assert_eq!(parts, [(1, "10".to_string()), (2, "10.5".to_string())]);
+        assert_eq!(
+            series_index.get_series("episode-10").unwrap().title,
+            "Big Interview"
+        );
+        assert!(series_index.get_series("missing").is_none());
+    }
+}
@@ -0,0 +1,122 @@
+//! Periodic refreshing of the podcast cache and search index.
+
+use crate::fdr_cache::FdrCache;
+use crate::http::get_all_podcasts;
+use crate::metrics::Metrics;
+use crate::search::SearchBackend;
+use crate::tag_normalizer::TagNormalizer;
+use crate::transcripts::TranscriptStore;
+use chrono::{DateTime, Utc};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Outcome of the most recent podcast syncs. Cloning is cheap, and all clones
+/// share the same underlying state.
+#[derive(Clone, Default)]
+pub struct SyncStatus {
+    inner: Arc<RwLock<SyncStatusInner>>,
+}
+
+#[derive(Default)]
+struct SyncStatusInner {
+    last_sync_time_or: Option<DateTime<Utc>>,
+    last_successful_sync_time_or: Option<DateTime<Utc>>,
+    last_sync_error_or: Option<String>,
+}
+
+impl SyncStatus {
+    pub fn record_success(&self) {
+        self.record_success_at(Utc::now());
+    }
+
+    pub fn record_success_at(&self, time: DateTime<Utc>) {
+        let mut inner = self.inner.write().unwrap();
+        inner.last_sync_time_or = Some(time);
+        inner.last_successful_sync_time_or = Some(time);
+        inner.last_sync_error_or = None;
+    }
+
+    pub fn record_failure(&self, error: String) {
+        let mut inner = self.inner.write().unwrap();
+        inner.last_sync_time_or = Some(Utc::now());
+        inner.last_sync_error_or = Some(error);
+    }
+
+    pub fn get_last_sync_time(&self) -> Option<DateTime<Utc>> {
+        self.inner.read().unwrap().last_sync_time_or
+    }
+
+    pub fn get_last_successful_sync_time(&self) -> Option<DateTime<Utc>> {
+        self.inner.read().unwrap().last_successful_sync_time_or
+    }
+
+    /// Error from the most recent sync, or `None` if it succeeded.
+    pub fn get_last_sync_error(&self) -> Option<String> {
+        self.inner.read().unwrap().last_sync_error_or.clone()
+    }
+}
+
+/// Fetches all podcasts and ingests them into the search index and then the cache.
+#[tracing::instrument(skip_all)]
+async fn sync_podcasts(
+    fdr_cache: &mut FdrCache,
+    search_backend: &SearchBackend,
+    metrics: &Metrics,
+    sync_status: &SyncStatus,
+    tag_normalizer: &TagNormalizer,
+    transcript_store: &TranscriptStore,
+) {
+    tracing::info!("Syncing podcasts");
+    let start_time = Instant::now();
+    let all_podcasts = match get_all_podcasts(tag_normalizer, transcript_store).await {
+        Ok(all_podcasts) => all_podcasts,
+        Err(err) => {
+            tracing::error!(error = %err, "Failed to fetch podcasts");
+            metrics.record_failed_sync(start_time.elapsed());
+            sync_status.record_failure(format!("Failed to fetch podcasts: {}", err));
+            return;
+        }
+    };
+    if let Err(err) = search_backend.ingest_podcasts(all_podcasts.iter()).await {
+        tracing::error!(error = %err, "Failed to ingest podcasts");
+        metrics.record_failed_sync(start_time.elapsed());
+        sync_status.record_failure(format!("Failed to ingest podcasts: {}", err));
+        return;
+    }
+    fdr_cache.ingest_podcasts(all_podcasts.into_iter());
+    metrics.record_successful_sync(start_time.elapsed(), fdr_cache.get_podcast_count());
+    sync_status.record_success();
+    tracing::info!(
+        podcast_count = fdr_cache.get_podcast_count(),
+        duration_ms = start_time.elapsed().as_millis() as u64,
+        "Finished syncing podcasts"
+    );
+}
+
+/// Spawns a task that periodically loads new podcasts.
+pub fn spawn_sync_task(
+    mut fdr_cache: FdrCache,
+    search_backend: SearchBackend,
+    metrics: Metrics,
+    sync_status: SyncStatus,
+    tag_normalizer: TagNormalizer,
+    transcript_store: TranscriptStore,
+) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SYNC_INTERVAL);
+        loop {
+            interval.tick().await;
+            sync_podcasts(
+                &mut fdr_cache,
+                &search_backend,
+                &metrics,
+                &sync_status,
+                &tag_normalizer,
+                &transcript_store,
+            )
+            .await;
+        }
+    });
+}
@@ -0,0 +1,162 @@
+//! Precomputed summary of every tag in the podcast cache, so that tags can be
+//! listed without searching over the whole catalogue.
+
+use crate::podcast::{Podcast, PodcastTag};
+use serde::Serialize;
+use std::collections::HashMap;
+use utoipa::ToSchema;
+
+/// Order that tags are listed in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TagOrder {
+    /// Alphabetical by tag.
+    Name,
+    /// Most used tags first, then alphabetical.
+    Popularity,
+}
+
+impl std::str::FromStr for TagOrder {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "name" => Ok(Self::Name),
+            "popularity" => Ok(Self::Popularity),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TagSummary {
+    #[schema(value_type = String)]
+    tag: PodcastTag,
+    /// Number of podcasts with the tag.
+    count: usize,
+    /// Unix timestamp (in seconds) of when the first podcast with the tag was published.
+    first_episode_time: i64,
+    /// Unix timestamp (in seconds) of when the latest podcast with the tag was published.
+    last_episode_time: i64,
+}
+
+impl TagSummary {
+    pub fn get_tag(&self) -> &PodcastTag {
+        &self.tag
+    }
+
+    pub fn get_count(&self) -> usize {
+        self.count
+    }
+}
+
+#[derive(Default)]
+pub struct TagIndex {
+    // Sorted by tag.
+    summaries: Vec<TagSummary>,
+    // Indices into `summaries`, in popularity order.
+    popularity_order: Vec<usize>,
+}
+
+impl TagIndex {
+    pub fn new<'a>(podcasts: impl Iterator<Item = &'a Podcast>) -> Self {
+        let mut summaries_by_tag: HashMap<&PodcastTag, TagSummary> = HashMap::new();
+        for podcast in podcasts {
+            let create_time = podcast.get_create_time();
+            for tag in podcast.get_tags() {
+                let summary = summaries_by_tag.entry(tag).or_insert_with(|| TagSummary {
+                    tag: tag.clone(),
+                    count: 0,
+                    first_episode_time: create_time,
+                    last_episode_time: create_time,
+                });
+                summary.count += 1;
+                summary.first_episode_time = summary.first_episode_time.min(create_time);
+                summary.last_episode_time = summary.last_episode_time.max(create_time);
+            }
+        }
+
+        let mut summaries: Vec<TagSummary> = summaries_by_tag.into_values().collect();
+        summaries.sort_by(|summary_one, summary_two| {
+            summary_one.tag.get_key().cmp(summary_two.tag.get_key())
+        });
+
+        // Sorting is stable, so tags with equal counts stay in alphabetical order.
+        let mut popularity_order: Vec<usize> = (0..summaries.len()).collect();
+        popularity_order.sort_by(|i, j| summaries[*j].count.cmp(&summaries[*i].count));
+
+        Self {
+            summaries,
+            popularity_order,
+        }
+    }
+
+    pub fn get_tag_count(&self) -> usize {
+        self.summaries.len()
+    }
+
+    /// All tags that start with the prefix, in the given order.
+    pub fn list(&self, order: TagOrder, prefix: &str) -> Vec<&TagSummary> {
+        // Tags starting with the prefix are contiguous in alphabetical order.
+        let start = self
+            .summaries
+            .partition_point(|summary| summary.tag.get_key() < prefix);
+        let end = start
+            + self.summaries[start..]
+                .partition_point(|summary| summary.tag.get_key().starts_with(prefix));
+
+        match order {
+            TagOrder::Name => self.summaries[start..end].iter().collect(),
+            TagOrder::Popularity => self
+                .popularity_order
+                .iter()
+                .filter(|i| (start..end).contains(*i))
+                .map(|i| &self.summaries[*i])
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::create_test_podcast;
+
+    #[test]
+    fn test_tag_index() {
+        let podcasts = [
+            create_test_podcast("1", "", 0, 100, &["free will", "anarchy"]),
+            create_test_podcast("2", "", 0, 300, &["free will", "freedom"]),
+            create_test_podcast("3", "", 0, 200, &["freedom", "free will"]),
+        ];
+        let tag_index = TagIndex::new(podcasts.iter());
+        assert_eq!(tag_index.get_tag_count(), 3);
+
+        let list_tags = |order: TagOrder, prefix: &str| -> Vec<String> {
+            tag_index
+                .list(order, prefix)
+                .iter()
+                .map(|summary| summary.tag.clone_to_string())
+                .collect()
+        };
+        assert_eq!(
+            list_tags(TagOrder::Name, ""),
+            ["anarchy", "free will", "freedom"]
+        );
+        assert_eq!(
+            list_tags(TagOrder::Popularity, ""),
+            ["free will", "freedom", "anarchy"]
+        );
+        assert_eq!(
+            list_tags(TagOrder::Popularity, "free"),
+            ["free will", "freedom"]
+        );
+        assert_eq!(list_tags(TagOrder::Name, "freed"), ["freedom"]);
+        assert!(list_tags(TagOrder::Name, "z").is_empty());
+
+        let free_will = tag_index.list(TagOrder::Name, "free will")[0];
+        assert_eq!(free_will.count, 3);
+        assert_eq!(free_will.first_episode_time, 100);
+        assert_eq!(free_will.last_episode_time, 300);
+    }
+}
@@ -0,0 +1,251 @@
+//! Normalization of free-text tags, so that tags which only differ in case,
+//! spacing, Unicode representation or spelling are treated as the same tag.
+//!
+//! Every tag is normalized as podcasts are ingested and as searches are made.
+//! Tags are compared by a case folded key, but are displayed as they're written.
+//! Spelling variants are merged through an alias table, read from a JSON file
+//! that maps each canonical tag to its aliases. Canonical tags are displayed
+//! as they're written in the file:
+//!
+//! ```json
+//! {
+//!   "aliases": {
+//!     "peaceful parenting": ["gentle parenting", "peaceful-parenting"]
+//!   }
+//! }
+//! ```
+
+use crate::podcast::{Podcast, PodcastTag};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Deserialize)]
+struct TagAliasesFile {
+    aliases: HashMap<String, Vec<String>>,
+}
+
+#[derive(Clone, Default)]
+pub struct TagNormalizer {
+    // Keyed by normalized alias. Values are normalized too.
+    canonical_tags_by_alias: Arc<HashMap<String, String>>,
+    // Canonical tags as they're written in the alias table, keyed by normalized tag.
+    canonical_tag_names: Arc<HashMap<String, String>>,
+}
+
+impl TagNormalizer {
+    fn new(aliases_by_canonical_tag: HashMap<String, Vec<String>>) -> Result<Self, String> {
+        let mut canonical_tags_by_alias = HashMap::new();
+        let mut canonical_tag_names = HashMap::new();
+        for (canonical_tag_name, aliases) in &aliases_by_canonical_tag {
+            let canonical_tag = normalize_text(canonical_tag_name);
+            canonical_tag_names.insert(
+                canonical_tag.clone(),
+                collapse_whitespace(canonical_tag_name),
+            );
+            for alias in aliases {
+                let alias = normalize_text(alias);
+                if alias == canonical_tag {
+                    continue;
+                }
+                if let Some(other_canonical_tag) =
+                    canonical_tags_by_alias.insert(alias.clone(), canonical_tag.clone())
+                {
+                    if other_canonical_tag != canonical_tag {
+                        return Err(format!(
+                            "Tag alias '{}' is an alias of both '{}' and '{}'.",
+                            alias, other_canonical_tag, canonical_tag
+                        ));
+                    }
+                }
+            }
+        }
+
+        // Aliases are only resolved once, so a canonical tag can't also be an alias.
+        for canonical_tag in canonical_tags_by_alias.values() {
+            if let Some(other_canonical_tag) = canonical_tags_by_alias.get(canonical_tag) {
+                return Err(format!(
+                    "Tag '{}' is a canonical tag, but is also an alias of '{}'.",
+                    canonical_tag, other_canonical_tag
+                ));
+            }
+        }
+
+        Ok(Self {
+            canonical_tags_by_alias: Arc::from(canonical_tags_by_alias),
+            canonical_tag_names: Arc::from(canonical_tag_names),
+        })
+    }
+
+    /// Loads the alias table from the file at the given path. No aliases are
+    /// used if the file doesn't exist, but tags are still normalized.
+    pub fn new_with_file(file_path: &Path) -> Result<Self, Box<dyn Error>> {
+        if !file_path.exists() {
+            tracing::warn!(path = %file_path.display(), "Tag alias file doesn't exist, so no aliases are used");
+            return Ok(Self::default());
+        }
+        let file: TagAliasesFile = serde_json::from_slice(&std::fs::read(file_path)?)?;
+        Ok(Self::new(file.aliases)?)
+    }
+
+    /// Returns the canonical form of a tag. Tags that aren't in the alias
+    /// table keep how they're written, other than their whitespace.
+    pub fn normalize(&self, raw_tag: &str) -> PodcastTag {
+        let tag = normalize_text(raw_tag);
+        let canonical_tag = self.canonical_tags_by_alias.get(&tag).unwrap_or(&tag);
+        match self.canonical_tag_names.get(canonical_tag) {
+            Some(canonical_tag_name) => PodcastTag::new(canonical_tag_name.clone()),
+            None => PodcastTag::new(collapse_whitespace(raw_tag)),
+        }
+    }
+
+    /// Normalizes the tags of every podcast, and then writes each tag the same
+    /// way on every podcast. Tags that aren't in the alias table are written
+    /// the way that most podcasts write them.
+    pub fn normalize_podcast_tags(&self, podcasts: &mut [Podcast]) {
+        for podcast in podcasts.iter_mut() {
+            podcast.normalize_tags(self);
+        }
+
+        let mut name_counts_by_tag: HashMap<&PodcastTag, HashMap<&str, usize>> = HashMap::new();
+        for podcast in podcasts.iter() {
+            for tag in podcast.get_tags() {
+                *name_counts_by_tag
+                    .entry(tag)
+                    .or_default()
+                    .entry(tag.to_string())
+                    .or_default() += 1;
+            }
+        }
+        // Ties go to the name that sorts first, so that the choice doesn't depend on hash order.
+        let tags: HashSet<PodcastTag> = name_counts_by_tag
+            .into_values()
+            .filter_map(|name_counts| {
+                name_counts
+                    .into_iter()
+                    .max_by(|(name_one, count_one), (name_two, count_two)| {
+                        count_one
+                            .cmp(count_two)
+                            .then_with(|| name_two.cmp(name_one))
+                    })
+                    .map(|(name, _)| PodcastTag::new(name.to_string()))
+            })
+            .collect();
+
+        for podcast in podcasts.iter_mut() {
+            let podcast_tags = podcast
+                .get_tags()
+                .iter()
+                .map(|tag| tags.get(tag).unwrap().clone())
+                .collect();
+            podcast.set_tags(podcast_tags);
+        }
+    }
+
+    pub fn get_alias_count(&self) -> usize {
+        self.canonical_tags_by_alias.len()
+    }
+}
+
+/// Applies Unicode compatibility normalization and case folding, and collapses
+/// each run of whitespace into a single space. Unlike `TagNormalizer::normalize`,
+/// this doesn't resolve aliases, so it can be used on partial tags.
+pub fn normalize_text(text: &str) -> String {
+    // Case folding can produce text that is no longer normalized, so it's
+    // normalized again afterwards.
+    let folded = caseless::default_case_fold_str(&text.nfkc().collect::<String>());
+    let normalized: String = folded.nfkc().collect();
+    collapse_whitespace(&normalized)
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::create_test_podcast;
+
+    #[test]
+    fn test_normalize() {
+        let mut aliases = HashMap::new();
+        aliases.insert(
+            "Peaceful Parenting".to_string(),
+            vec![
+                "gentle parenting".to_string(),
+                "PEACEFUL-PARENTING".to_string(),
+            ],
+        );
+        let tag_normalizer = TagNormalizer::new(aliases).unwrap();
+        let normalize = |tag: &str| {
+            let tag = tag_normalizer.normalize(tag);
+            (tag.clone_to_string(), tag.get_key().to_string())
+        };
+
+        assert_eq!(
+            normalize("  Free\tWill  "),
+            ("Free Will".to_string(), "free will".to_string())
+        );
+        assert_eq!(
+            normalize("Straße"),
+            ("Straße".to_string(), "strasse".to_string())
+        );
+        // Full-width letters and a non-breaking space.
+        assert_eq!(normalize("ＦＤＲ\u{a0}Live").1, "fdr live");
+        // Precomposed and combining accents.
+        assert_eq!(normalize("caf\u{e9}").1, normalize("cafe\u{301}").1);
+        // Canonical tags are written the way the alias table writes them.
+        for tag in [
+            "Gentle  Parenting",
+            "peaceful-parenting",
+            "peaceful parenting",
+        ] {
+            assert_eq!(
+                normalize(tag),
+                (
+                    "Peaceful Parenting".to_string(),
+                    "peaceful parenting".to_string()
+                )
+            );
+        }
+
+        let mut conflicting_aliases = HashMap::new();
+        conflicting_aliases.insert("a".to_string(), vec!["b".to_string()]);
+        conflicting_aliases.insert("c".to_string(), vec!["B".to_string()]);
+        assert!(TagNormalizer::new(conflicting_aliases).is_err());
+
+        let mut chained_aliases = HashMap::new();
+        chained_aliases.insert("a".to_string(), vec!["b".to_string()]);
+        chained_aliases.insert("b".to_string(), vec!["c".to_string()]);
+        assert!(TagNormalizer::new(chained_aliases).is_err());
+    }
+
+    #[test]
+    fn test_normalize_podcast_tags() {
+        let mut podcasts = [
+            create_test_podcast("1", "", 0, 0, &["Free Will", "anarchy"]),
+            create_test_podcast("2", "", 0, 0, &["free will", "Anarchy"]),
+            create_test_podcast("3", "", 0, 0, &["FREE  WILL"]),
+            create_test_podcast("4", "", 0, 0, &["free will"]),
+        ];
+        TagNormalizer::default().normalize_podcast_tags(&mut podcasts);
+
+        let get_tag_names = |podcast: &Podcast| -> Vec<String> {
+            let mut tag_names: Vec<String> = podcast
+                .get_tags()
+                .iter()
+                .map(PodcastTag::clone_to_string)
+                .collect();
+            tag_names.sort();
+            tag_names
+        };
+        // The most common way of writing a tag wins, and ties go to the first name in sort order.
+        assert_eq!(get_tag_names(&podcasts[0]), ["Anarchy", "free will"]);
+        assert_eq!(get_tag_names(&podcasts[1]), ["Anarchy", "free will"]);
+        assert_eq!(get_tag_names(&podcasts[2]), ["free will"]);
+    }
+}
@@ -0,0 +1,272 @@
+//! Hierarchy of categories that podcast tags are grouped into.
+//!
+//! Tags themselves are flat, so the taxonomy is defined separately in a JSON
+//! file, where each category lists its own tags and any subcategories:
+//!
+//! ```json
+//! {
+//!   "categories": [
+//!     {
+//!       "name": "Philosophy",
+//!       "tags": ["philosophy"],
+//!       "subcategories": [{ "name": "Ethics", "tags": ["ethics", "morality"] }]
+//!     }
+//!   ]
+//! }
+//! ```
+
+use crate::podcast::{Podcast, PodcastTag};
+use crate::tag_normalizer::TagNormalizer;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CategoryDefinition {
+    name: String,
+    #[serde(default)]
+    tags: Vec<PodcastTag>,
+    #[serde(default)]
+    subcategories: Vec<CategoryDefinition>,
+}
+
+#[derive(Deserialize)]
+struct TaxonomyFile {
+    categories: Vec<CategoryDefinition>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum TaxonomyNodeKind {
+    Category,
+    Tag,
+}
+
+/// A category or tag in the taxonomy, along with how many podcasts fall under it.
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TaxonomyNode {
+    name: String,
+    kind: TaxonomyNodeKind,
+    /// Number of podcasts with this tag, or with any tag under this category.
+    episode_count: usize,
+    /// Subcategories followed by the category's own tags. Always empty for tags.
+    children: Vec<TaxonomyNode>,
+}
+
+#[derive(Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TaxonomyHierarchy {
+    episode_count: usize,
+    /// Number of podcasts without any tag that belongs to a category.
+    uncategorized_episode_count: usize,
+    categories: Vec<TaxonomyNode>,
+}
+
+#[derive(Clone, Default)]
+pub struct Taxonomy {
+    categories: Arc<Vec<CategoryDefinition>>,
+    // Every tag under each category, including those of its subcategories. Sorted and deduplicated.
+    tags_by_category: Arc<HashMap<String, Vec<PodcastTag>>>,
+}
+
+impl Taxonomy {
+    fn new(
+        mut categories: Vec<CategoryDefinition>,
+        tag_normalizer: &TagNormalizer,
+    ) -> Result<Self, String> {
+        for category in &mut categories {
+            Self::normalize_tags(category, tag_normalizer);
+        }
+        let mut tags_by_category = HashMap::new();
+        for category in &categories {
+            Self::collect_tags(category, &mut tags_by_category)?;
+        }
+        Ok(Self {
+            categories: Arc::from(categories),
+            tags_by_category: Arc::from(tags_by_category),
+        })
+    }
+
+    /// Loads the taxonomy from the file at the given path. An empty taxonomy
+    /// is returned if the file doesn't exist.
+    pub fn new_with_file(
+        file_path: &Path,
+        tag_normalizer: &TagNormalizer,
+    ) -> Result<Self, Box<dyn Error>> {
+        if !file_path.exists() {
+            tracing::warn!(path = %file_path.display(), "Taxonomy file doesn't exist, so no categories are used");
+            return Ok(Self::default());
+        }
+        let file: TaxonomyFile = serde_json::from_slice(&std::fs::read(file_path)?)?;
+        Ok(Self::new(file.categories, tag_normalizer)?)
+    }
+
+    /// Groups the mock podcasts' tags into five categories with two subcategories each.
+    pub fn new_mock(tag_normalizer: &TagNormalizer) -> Self {
+        let categories = (0..5)
+            .map(|i| CategoryDefinition {
+                name: format!("Category #{}", i),
+                tags: Vec::new(),
+                subcategories: (0..2)
+                    .map(|j| CategoryDefinition {
+                        name: format!("Subcategory #{}.{}", i, j),
+                        tags: (0..10)
+                            .map(|k| PodcastTag::new(format!("Tag #{}", i * 20 + j * 10 + k)))
+                            .collect(),
+                        subcategories: Vec::new(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Self::new(categories, tag_normalizer).unwrap()
+    }
+
+    // Podcasts' tags are normalized, so the taxonomy's must be too in order to match them.
+    fn normalize_tags(category: &mut CategoryDefinition, tag_normalizer: &TagNormalizer) {
+        for tag in &mut category.tags {
+            *tag = tag_normalizer.normalize(tag.to_string());
+        }
+        for subcategory in &mut category.subcategories {
+            Self::normalize_tags(subcategory, tag_normalizer);
+        }
+    }
+
+    // Returns every tag under the category, and records it along with those of all subcategories.
+    fn collect_tags(
+        category: &CategoryDefinition,
+        tags_by_category: &mut HashMap<String, Vec<PodcastTag>>,
+    ) -> Result<Vec<PodcastTag>, String> {
+        let mut tags = category.tags.clone();
+        for subcategory in &category.subcategories {
+            tags.extend(Self::collect_tags(subcategory, tags_by_category)?);
+        }
+        tags.sort_by(|tag_one, tag_two| tag_one.to_string().cmp(tag_two.to_string()));
+        tags.dedup();
+
+        if tags.is_empty() {
+            return Err(format!("Category '{}' has no tags.", category.name));
+        }
+        if tags_by_category
+            .insert(category.name.clone(), tags.clone())
+            .is_some()
+        {
+            return Err(format!(
+                "Category '{}' is defined more than once.",
+                category.name
+            ));
+        }
+        Ok(tags)
+    }
+
+    /// Every tag under the category, including those of its subcategories.
+    /// Returns `None` if there is no such category.
+    pub fn get_category_tags(&self, category: &str) -> Option<&[PodcastTag]> {
+        self.tags_by_category.get(category).map(Vec::as_slice)
+    }
+
+    pub fn get_hierarchy(&self, podcasts: &[&Podcast]) -> TaxonomyHierarchy {
+        let categorized_tags: HashSet<&PodcastTag> =
+            self.tags_by_category.values().flatten().collect();
+        TaxonomyHierarchy {
+            episode_count: podcasts.len(),
+            uncategorized_episode_count: podcasts
+                .iter()
+                .filter(|podcast| {
+                    !podcast
+                        .get_tags()
+                        .iter()
+                        .any(|tag| categorized_tags.contains(tag))
+                })
+                .count(),
+            categories: self
+                .categories
+                .iter()
+                .map(|category| self.build_node(category, podcasts))
+                .collect(),
+        }
+    }
+
+    fn build_node(&self, category: &CategoryDefinition, podcasts: &[&Podcast]) -> TaxonomyNode {
+        let tags: HashSet<&PodcastTag> = self.tags_by_category[&category.name].iter().collect();
+        let mut children: Vec<TaxonomyNode> = category
+            .subcategories
+            .iter()
+            .map(|subcategory| self.build_node(subcategory, podcasts))
+            .collect();
+        children.extend(category.tags.iter().map(|tag| {
+            TaxonomyNode {
+                name: tag.clone_to_string(),
+                kind: TaxonomyNodeKind::Tag,
+                episode_count: podcasts
+                    .iter()
+                    .filter(|podcast| podcast.get_tags().contains(tag))
+                    .count(),
+                children: Vec::new(),
+            }
+        }));
+
+        TaxonomyNode {
+            name: category.name.clone(),
+            kind: TaxonomyNodeKind::Category,
+            // Counted directly rather than summed over children, since a
+            // podcast with several of the category's tags must only count once.
+            episode_count: podcasts
+                .iter()
+                .filter(|podcast| podcast.get_tags().iter().any(|tag| tags.contains(tag)))
+                .count(),
+            children,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock::create_test_podcast;
+
+    #[test]
+    fn test_taxonomy() {
+        let file: TaxonomyFile = serde_json::from_str(
+            r#"{"categories": [{"name": "A", "tags": ["a"], "subcategories": [{"name": "B", "tags": ["b", "c"]}]}]}"#,
+        )
+        .unwrap();
+        let taxonomy = Taxonomy::new(file.categories, &TagNormalizer::default()).unwrap();
+        let tag = |tag: &str| PodcastTag::new(tag.to_string());
+        assert_eq!(
+            taxonomy.get_category_tags("A"),
+            Some([tag("a"), tag("b"), tag("c")].as_slice())
+        );
+        assert_eq!(taxonomy.get_category_tags("missing"), None);
+
+        let podcasts = [
+            create_test_podcast("1", "", 0, 0, &["a", "b"]),
+            create_test_podcast("2", "", 0, 0, &["b", "c"]),
+            create_test_podcast("3", "", 0, 0, &["d"]),
+        ];
+        let podcasts: Vec<&Podcast> = podcasts.iter().collect();
+        let hierarchy = taxonomy.get_hierarchy(&podcasts);
+        assert_eq!(hierarchy.episode_count, 3);
+        assert_eq!(hierarchy.uncategorized_episode_count, 1);
+        let category_a = &hierarchy.categories[0];
+        assert_eq!(category_a.episode_count, 2);
+        assert_eq!(category_a.children[0].name, "B");
+        assert_eq!(category_a.children[0].episode_count, 2);
+        assert_eq!(category_a.children[1].name, "a");
+        assert_eq!(category_a.children[1].episode_count, 1);
+
+        assert!(Taxonomy::new(
+            vec![CategoryDefinition {
+                name: "Empty".to_string(),
+                tags: Vec::new(),
+                subcategories: Vec::new(),
+            }],
+            &TagNormalizer::default()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,561 @@
+//! Transcripts of podcast episodes, which are read at startup from a local
+//! directory rather than fetched from upstream.
+//!
+//! Each file is named after the number of the podcast that it transcribes, and
+//! its extension gives its format: `1234.txt` (plain text, with paragraphs
+//! separated by blank lines), `1234.srt` (SubRip) or `1234.vtt` (WebVTT).
+//! Special episodes use their ID instead, such as `special-0123456789ab.vtt`.
+
+use crate::podcast::{Podcast, PodcastNumber};
+use crate::tag_normalizer::normalize_text;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::path::Path;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// File format of a transcript. Variants are ordered by preference, since
+/// timed formats are more useful if a podcast has several transcripts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum TranscriptFormat {
+    Vtt,
+    Srt,
+    Text,
+}
+
+impl std::str::FromStr for TranscriptFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "vtt" => Ok(Self::Vtt),
+            "srt" => Ok(Self::Srt),
+            "text" => Ok(Self::Text),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for TranscriptFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Vtt => write!(f, "vtt"),
+            Self::Srt => write!(f, "srt"),
+            Self::Text => write!(f, "text"),
+        }
+    }
+}
+
+impl TranscriptFormat {
+    fn from_extension(extension: &str) -> Option<Self> {
+        match extension.to_ascii_lowercase().as_str() {
+            "vtt" => Some(Self::Vtt),
+            "srt" => Some(Self::Srt),
+            "txt" => Some(Self::Text),
+            _ => None,
+        }
+    }
+
+    pub fn get_mime_type(&self) -> &'static str {
+        match self {
+            Self::Vtt => "text/vtt",
+            Self::Srt => "application/x-subrip",
+            Self::Text => "text/plain",
+        }
+    }
+}
+
+/// A single caption, or a paragraph of a plain-text transcript.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TranscriptCue {
+    // Offsets in milliseconds, which plain-text transcripts don't have.
+    start_ms: Option<u64>,
+    end_ms: Option<u64>,
+    text: String,
+    // Normalized words of the text, so that searches don't have to re-normalize it.
+    words: Vec<String>,
+}
+
+impl TranscriptCue {
+    fn new(start_ms: Option<u64>, end_ms: Option<u64>, text: String) -> Self {
+        let words = normalize_text(&text)
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_string)
+            .collect();
+        Self {
+            start_ms,
+            end_ms,
+            text,
+            words,
+        }
+    }
+}
+
+/// A part of a transcript that matches a search.
+#[derive(Clone, Debug, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptMatch {
+    /// Offset (in seconds) from the start of the episode. Always `null` for plain-text transcripts.
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+    text: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Transcript {
+    cues: Vec<TranscriptCue>,
+}
+
+impl Transcript {
+    pub fn parse(contents: &str, format: TranscriptFormat) -> Result<Self, String> {
+        let contents = contents
+            .trim_start_matches('\u{feff}')
+            .replace("\r\n", "\n");
+        let blocks = contents
+            .split("\n\n")
+            .map(str::trim)
+            .filter(|block| !block.is_empty());
+
+        let mut cues = Vec::new();
+        match format {
+            TranscriptFormat::Text => {
+                for block in blocks {
+                    cues.push(TranscriptCue::new(None, None, join_lines(block.lines())));
+                }
+            }
+            TranscriptFormat::Srt | TranscriptFormat::Vtt => {
+                let mut blocks = blocks;
+                if format == TranscriptFormat::Vtt {
+                    match blocks.next() {
+                        Some(header) if header.starts_with("WEBVTT") => {}
+                        _ => return Err("WebVTT transcript has no header.".to_string()),
+                    }
+                }
+                for block in blocks {
+                    // Blocks without timings are SRT cue numbers or WebVTT notes, styles and regions.
+                    let mut lines = block.lines().skip_while(|line| !line.contains("-->"));
+                    let timing = match lines.next() {
+                        Some(timing) => timing,
+                        None => continue,
+                    };
+                    let (start, end) = timing.split_once("-->").unwrap();
+                    // WebVTT cue settings may follow the end time.
+                    let end = end.split_whitespace().next().unwrap_or_default();
+                    let parse = |time: &str| {
+                        parse_cue_time(time.trim())
+                            .ok_or_else(|| format!("Invalid cue timing '{}'.", timing))
+                    };
+                    cues.push(TranscriptCue::new(
+                        Some(parse(start)?),
+                        Some(parse(end)?),
+                        join_lines(lines.map(strip_cue_tags)),
+                    ));
+                }
+            }
+        }
+        Ok(Self { cues })
+    }
+
+    /// The whole transcript as plain text, with a line per cue.
+    pub fn get_text(&self) -> String {
+        self.cues
+            .iter()
+            .map(|cue| cue.text.as_str())
+            .collect::<Vec<&str>>()
+            .join("\n")
+    }
+
+    /// Whether every cue has a timing, which is needed to convert the transcript to captions.
+    fn is_timed(&self) -> bool {
+        self.cues.iter().all(|cue| cue.start_ms.is_some())
+    }
+
+    /// Formats that the transcript can be rendered in, most preferred first.
+    pub fn get_formats(&self) -> Vec<TranscriptFormat> {
+        if self.is_timed() {
+            vec![
+                TranscriptFormat::Vtt,
+                TranscriptFormat::Srt,
+                TranscriptFormat::Text,
+            ]
+        } else {
+            vec![TranscriptFormat::Text]
+        }
+    }
+
+    /// Converts the transcript to the given format. Returns `None` if it's a
+    /// plain-text transcript, which can't be converted to captions.
+    pub fn render(&self, format: TranscriptFormat) -> Option<String> {
+        if format != TranscriptFormat::Text && !self.is_timed() {
+            return None;
+        }
+
+        let mut rendered = String::new();
+        match format {
+            TranscriptFormat::Text => rendered = self.get_text(),
+            TranscriptFormat::Srt => {
+                for (i, cue) in self.cues.iter().enumerate() {
+                    rendered.push_str(&format!(
+                        "{}\n{} --> {}\n{}\n\n",
+                        i + 1,
+                        format_cue_time(cue.start_ms?, ','),
+                        format_cue_time(cue.end_ms?, ','),
+                        cue.text
+                    ));
+                }
+            }
+            TranscriptFormat::Vtt => {
+                rendered.push_str("WEBVTT\n\n");
+                for cue in &self.cues {
+                    // Unlike SRT, WebVTT text can contain markup, so it has to be escaped.
+                    let text = cue
+                        .text
+                        .replace('&', "&amp;")
+                        .replace('<', "&lt;")
+                        .replace('>', "&gt;");
+                    rendered.push_str(&format!(
+                        "{} --> {}\n{}\n\n",
+                        format_cue_time(cue.start_ms?, '.'),
+                        format_cue_time(cue.end_ms?, '.'),
+                        text
+                    ));
+                }
+            }
+        }
+        Some(rendered)
+    }
+
+    /// Cues containing the most words of the query (or words starting with
+    /// them), in the order that they're spoken.
+    pub fn find_matches(&self, query: &str, limit: usize) -> Vec<TranscriptMatch> {
+        let mut terms: Vec<String> = normalize_text(query)
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|term| !term.is_empty())
+            .map(str::to_string)
+            .collect();
+        terms.sort();
+        terms.dedup();
+
+        let mut scored_cues: Vec<(usize, &TranscriptCue)> = self
+            .cues
+            .iter()
+            .map(|cue| {
+                let score = terms
+                    .iter()
+                    .filter(|term| cue.words.iter().any(|word| word.starts_with(term.as_str())))
+                    .count();
+                (score, cue)
+            })
+            .filter(|(score, _)| *score > 0)
+            .collect();
+        // Sorting is stable, so equally good cues stay in the order that they're spoken.
+        scored_cues.sort_by(|(score_one, _), (score_two, _)| score_two.cmp(score_one));
+        scored_cues.truncate(limit);
+        scored_cues.sort_by_key(|(_, cue)| cue.start_ms);
+
+        let to_seconds = |ms: Option<u64>| ms.map(|ms| ms as f64 / 1000.0);
+        scored_cues
+            .into_iter()
+            .map(|(_, cue)| TranscriptMatch {
+                start_time: to_seconds(cue.start_ms),
+                end_time: to_seconds(cue.end_ms),
+                text: cue.text.clone(),
+            })
+            .collect()
+    }
+}
+
+/// Parses a cue time such as `01:02:03,456` (SRT) or `02:03.456` (WebVTT) into milliseconds.
+fn parse_cue_time(time: &str) -> Option<u64> {
+    let (time, ms) = time.split_once([',', '.'])?;
+    if ms.len() != 3 {
+        return None;
+    }
+    let mut seconds: u64 = 0;
+    let parts: Vec<&str> = time.split(':').collect();
+    if parts.len() < 2 || parts.len() > 3 {
+        return None;
+    }
+    for part in parts {
+        if part.is_empty() || !part.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        seconds = seconds * 60 + part.parse::<u64>().ok()?;
+    }
+    if !ms.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(seconds * 1000 + ms.parse::<u64>().ok()?)
+}
+
+/// Formats milliseconds as a cue time, such as `01:02:03,456`.
+fn format_cue_time(ms: u64, millisecond_separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        millisecond_separator,
+        ms % 1000
+    )
+}
+
+// Removes formatting and voice tags, such as `<i>` and `<v Speaker>`, from a line of a cue.
+fn strip_cue_tags(line: &str) -> String {
+    let mut text = String::new();
+    let mut is_in_tag = false;
+    for c in line.chars() {
+        match c {
+            '<' => is_in_tag = true,
+            '>' if is_in_tag => is_in_tag = false,
+            _ if !is_in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn join_lines<T: AsRef<str>>(lines: impl Iterator<Item = T>) -> String {
+    lines
+        .map(|line| line.as_ref().trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+#[derive(Clone, Default)]
+pub struct TranscriptStore {
+    transcripts_by_number: Arc<HashMap<PodcastNumber, Arc<Transcript>>>,
+}
+
+impl TranscriptStore {
+    /// Loads every transcript in the directory at the given path. No
+    /// transcripts are loaded if the directory doesn't exist. Files that can't
+    /// be read or parsed are skipped, so that one bad file doesn't stop the server
+    /// from starting.
+    pub fn new_with_dir(dir_path: &Path) -> Result<Self, Box<dyn Error>> {
+        if !dir_path.exists() {
+            tracing::warn!(path = %dir_path.display(), "Transcript directory doesn't exist, so no transcripts are loaded");
+            return Ok(Self::default());
+        }
+
+        let mut transcripts_by_number: HashMap<PodcastNumber, (TranscriptFormat, Transcript)> =
+            HashMap::new();
+        for entry in std::fs::read_dir(dir_path)? {
+            let path = entry?.path();
+            let format_or = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .and_then(TranscriptFormat::from_extension);
+            let number_or = path
+                .file_stem()
+                .and_then(|file_stem| file_stem.to_str())
+                .and_then(|file_stem| file_stem.parse::<PodcastNumber>().ok());
+            let (format, number) = match (format_or, number_or) {
+                (Some(format), Some(number)) => (format, number),
+                _ => {
+                    tracing::warn!(path = %path.display(), "Skipping file that isn't named like a transcript");
+                    continue;
+                }
+            };
+            if let Some((existing_format, _)) = transcripts_by_number.get(&number) {
+                if *existing_format <= format {
+                    continue;
+                }
+            }
+            let contents = match std::fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(error) => {
+                    tracing::warn!(path = %path.display(), error = %error, "Skipping unreadable transcript");
+                    continue;
+                }
+            };
+            match Transcript::parse(&contents, format) {
+                Ok(transcript) => {
+                    transcripts_by_number.insert(number, (format, transcript));
+                }
+                Err(error) => {
+                    tracing::warn!(path = %path.display(), error = %error, "Skipping invalid transcript");
+                }
+            }
+        }
+
+        Ok(Self {
+            transcripts_by_number: Arc::from(
+                transcripts_by_number
+                    .into_iter()
+                    .map(|(number, (_, transcript))| (number, Arc::from(transcript)))
+                    .collect::<HashMap<PodcastNumber, Arc<Transcript>>>(),
+            ),
+        })
+    }
+
+    /// Transcribes the first few mock podcasts.
+    pub fn new_mock() -> Self {
+        let transcripts_by_number = (1..=20)
+            .map(|num| {
+                let contents = format!(
+                    "WEBVTT\n\n00:00.000 --> 00:05.000\nWelcome to podcast #{}.\n\n00:05.000 --> 00:10.000\n<v Host>Today we're talking about tag #{}.",
+                    num, num
+                );
+                (
+                    PodcastNumber::from_integer(num),
+                    Arc::from(Transcript::parse(&contents, TranscriptFormat::Vtt).unwrap()),
+                )
+            })
+            .collect::<HashMap<PodcastNumber, Arc<Transcript>>>();
+        Self {
+            transcripts_by_number: Arc::from(transcripts_by_number),
+        }
+    }
+
+    pub fn get(&self, number: &PodcastNumber) -> Option<Arc<Transcript>> {
+        self.transcripts_by_number.get(number).cloned()
+    }
+
+    /// Records on each podcast which formats its transcript is available in, if it has one.
+    pub fn mark_transcribed_podcasts(&self, podcasts: &mut [Podcast]) {
+        for podcast in podcasts {
+            let transcript_formats = match self.get(podcast.get_podcast_number()) {
+                Some(transcript) => transcript.get_formats(),
+                None => Vec::new(),
+            };
+            podcast.set_transcript_formats(transcript_formats);
+        }
+    }
+
+    pub fn get_transcript_count(&self) -> usize {
+        self.transcripts_by_number.len()
+    }
+}
+
+/// A transcript rendered in a particular format.
+pub struct TranscriptDocument {
+    contents: String,
+    format: TranscriptFormat,
+}
+
+impl TranscriptDocument {
+    pub fn new(contents: String, format: TranscriptFormat) -> Self {
+        Self { contents, format }
+    }
+}
+
+// TODO - Abstract this into a procedural macro along with all other Responder impl blocks in other structs.
+impl<'r> rocket::response::Responder<'r, 'static> for TranscriptDocument {
+    fn respond_to(
+        self,
+        _request: &'r rocket::request::Request,
+    ) -> Result<rocket::response::Response<'static>, rocket::http::Status> {
+        let content_type = match self.format {
+            TranscriptFormat::Vtt => rocket::http::ContentType::new("text", "vtt"),
+            TranscriptFormat::Srt => rocket::http::ContentType::new("application", "x-subrip"),
+            TranscriptFormat::Text => rocket::http::ContentType::Plain,
+        };
+        rocket::Response::build()
+            .header(content_type)
+            .sized_body(self.contents.len(), std::io::Cursor::new(self.contents))
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_transcript() {
+        let srt = Transcript::parse(
+            "1\r\n00:00:01,000 --> 00:00:04,500\r\n<i>Hello</i> there,\r\nlisteners.\r\n\r\n2\r\n01:00:00,000 --> 01:00:02,000\r\nGoodbye & thanks.\r\n",
+            TranscriptFormat::Srt,
+        )
+        .unwrap();
+        let vtt = Transcript::parse(
+            "WEBVTT - Episode 1\n\nNOTE A comment\n\nintro\n00:01.000 --> 00:04.500 align:start\n<v Host>Hello there,\nlisteners.\n\n1:00:00.000 --> 1:00:02.000\nGoodbye &amp; thanks.",
+            TranscriptFormat::Vtt,
+        )
+        .unwrap();
+        assert_eq!(srt, vtt);
+        assert_eq!(srt.cues[0].start_ms, Some(1000));
+        assert_eq!(srt.cues[1].end_ms, Some(3_602_000));
+        assert_eq!(srt.get_text(), "Hello there, listeners.\nGoodbye & thanks.");
+
+        let text = Transcript::parse("First\nparagraph.\n\n\nSecond.", TranscriptFormat::Text);
+        assert_eq!(text.unwrap().get_text(), "First paragraph.\nSecond.");
+
+        assert!(Transcript::parse("00:01.000 --> 00:02.000\nHi", TranscriptFormat::Vtt).is_err());
+        assert!(Transcript::parse("00:01 --> 00:02\nHi", TranscriptFormat::Srt).is_err());
+    }
+
+    #[test]
+    fn test_render_transcript() {
+        let vtt = "WEBVTT\n\n00:00:01.000 --> 01:02:03.045\nFish &amp; chips\n\n01:02:03.045 --> 01:02:04.000\nBye.\n\n";
+        let transcript = Transcript::parse(vtt, TranscriptFormat::Vtt).unwrap();
+        assert_eq!(transcript.render(TranscriptFormat::Vtt).unwrap(), vtt);
+        let srt = transcript.render(TranscriptFormat::Srt).unwrap();
+        assert_eq!(
+            srt,
+            "1\n00:00:01,000 --> 01:02:03,045\nFish & chips\n\n2\n01:02:03,045 --> 01:02:04,000\nBye.\n\n"
+        );
+        assert_eq!(
+            Transcript::parse(&srt, TranscriptFormat::Srt).unwrap(),
+            transcript
+        );
+        assert_eq!(
+            transcript.render(TranscriptFormat::Text).unwrap(),
+            "Fish & chips\nBye."
+        );
+
+        let text = Transcript::parse("Untimed.", TranscriptFormat::Text).unwrap();
+        assert_eq!(text.get_formats(), [TranscriptFormat::Text]);
+        assert_eq!(text.render(TranscriptFormat::Srt), None);
+    }
+
+    #[test]
+    fn test_skip_bad_transcript_files() {
+        let dir = std::env::temp_dir().join(format!("fdr-transcripts-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("1.txt"), "Hello.").unwrap();
+        std::fs::write(dir.join("2.txt"), [0xff, 0xfe]).unwrap();
+        std::fs::write(dir.join("3.vtt"), "Not WebVTT").unwrap();
+
+        let transcript_store = TranscriptStore::new_with_dir(&dir).unwrap();
+        assert_eq!(transcript_store.get_transcript_count(), 1);
+        assert!(transcript_store
+            .get(&PodcastNumber::from_integer(1))
+            .is_some());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_matches() {
+        let transcript = Transcript::parse(
+            "WEBVTT\n\n00:00.000 --> 00:01.000\nFree will is real.\n\n00:01.000 --> 00:02.500\nWhat is freedom?\n\n00:02.500 --> 00:03.000\nFree WILL again.",
+            TranscriptFormat::Vtt,
+        )
+        .unwrap();
+        let match_texts = |query: &str, limit: usize| -> Vec<String> {
+            transcript
+                .find_matches(query, limit)
+                .into_iter()
+                .map(|transcript_match| transcript_match.text)
+                .collect()
+        };
+        assert_eq!(
+            match_texts("free will", 2),
+            ["Free will is real.", "Free WILL again."]
+        );
+        assert_eq!(match_texts("FREE", 5).len(), 3);
+        assert!(match_texts("slavery", 5).is_empty());
+        assert_eq!(
+            transcript.find_matches("freedom", 1)[0].start_time,
+            Some(1.0)
+        );
+    }
+}